@@ -0,0 +1,53 @@
+use std::borrow::Cow;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// Removes the ANSI escape sequences (colors, cursor movements, hyperlinks, ...) from a line of output.
+///
+/// Lines without any escape character are returned as-is, without allocating.
+pub fn strip(line: &str) -> Cow<'_, str> {
+    if !line.contains(ESC) {
+        return Cow::Borrowed(line);
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != ESC {
+            output.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            //? CSI sequences (as in `ESC [ 1 ; 31 m`) end with a byte within '@'..='~'.
+            Some('[') => {
+                for ch in chars.by_ref() {
+                    if ('@'..='~').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            //? OSC, DCS, APC, PM and SOS strings end with BEL or ST (`ESC \`).
+            Some(']' | 'P' | '_' | '^' | 'X') => {
+                while let Some(ch) = chars.next() {
+                    if ch == BEL {
+                        break;
+                    }
+                    if ch == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            //? character set designations (as in `ESC ( B`) take one more character.
+            Some('(' | ')' | '*' | '+') => {
+                chars.next();
+            }
+            //? other sequences are made of a single character.
+            Some(_) | None => {}
+        }
+    }
+
+    Cow::Owned(output)
+}
@@ -5,6 +5,7 @@ use crate::error::{Error, PersistError};
 
 pub static PID_FILE: &str = "daemon.pid";
 pub static SOCK_FILE: &str = "daemon.sock";
+pub static STATE_FILE: &str = "state.json";
 
 pub static PIDS_DIR: &str = "pids";
 pub static LOGS_DIR: &str = "logs";
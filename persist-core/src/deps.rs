@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::Error;
+
+/// Sorts processes into successive layers, such that every process comes after the ones it depends on.
+///
+/// The processes within a layer do not depend on each other, so they can be handled concurrently.  
+/// Dependencies on processes outside of the given ones are ignored.  
+/// Fails if some processes depend on each other in a cycle.
+pub fn layers(graph: &BTreeMap<String, Vec<String>>) -> Result<Vec<Vec<String>>, Error> {
+    let mut remaining: BTreeMap<&str, BTreeSet<&str>> = graph
+        .iter()
+        .map(|(name, deps)| {
+            let deps = deps
+                .iter()
+                .map(String::as_str)
+                .filter(|dep| graph.contains_key(*dep))
+                .collect();
+            (name.as_str(), deps)
+        })
+        .collect();
+
+    let mut layers = Vec::new();
+    while !remaining.is_empty() {
+        let layer: Vec<&str> = remaining
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(name, _)| *name)
+            .collect();
+
+        if layer.is_empty() {
+            return Err(Error::from(format!(
+                "processes depend on each other in a cycle ({})",
+                find_cycle(&remaining).join(" -> "),
+            )));
+        }
+
+        for name in layer.iter() {
+            remaining.remove(name);
+        }
+        for deps in remaining.values_mut() {
+            for name in layer.iter() {
+                deps.remove(name);
+            }
+        }
+
+        layers.push(layer.into_iter().map(String::from).collect());
+    }
+
+    Ok(layers)
+}
+
+/// Sorts processes such that every process comes after the ones it depends on.
+pub fn order(graph: &BTreeMap<String, Vec<String>>) -> Result<Vec<String>, Error> {
+    Ok(layers(graph)?.into_iter().flatten().collect())
+}
+
+/// Finds a cycle among processes that all (transitively) depend on each other, ending with where it started.
+fn find_cycle<'a>(graph: &BTreeMap<&'a str, BTreeSet<&'a str>>) -> Vec<&'a str> {
+    let mut path: Vec<&str> = Vec::new();
+    let mut current = match graph.keys().next() {
+        Some(name) => *name,
+        None => return path,
+    };
+
+    //? every remaining process depends on another remaining one, so following them always ends up in a loop.
+    while !path.contains(&current) {
+        path.push(current);
+        current = match graph.get(current).and_then(|deps| deps.iter().next()) {
+            Some(dep) => *dep,
+            None => break,
+        };
+    }
+
+    let start = path.iter().position(|name| *name == current).unwrap_or(0);
+    let mut cycle = path.split_off(start);
+    cycle.push(current);
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(name, deps)| {
+                let deps = deps.iter().map(|dep| dep.to_string()).collect();
+                (name.to_string(), deps)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chain() {
+        let graph = graph(&[("c", &["b"]), ("b", &["a"]), ("a", &[])]);
+        let layers = layers(&graph).unwrap();
+        assert_eq!(layers, vec![vec!["a"], vec!["b"], vec!["c"]]);
+        assert_eq!(order(&graph).unwrap(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn diamond() {
+        let graph = graph(&[("a", &[]), ("b", &["a"]), ("c", &["a"]), ("d", &["b", "c"])]);
+        let layers = layers(&graph).unwrap();
+        assert_eq!(layers, vec![vec!["a"], vec!["b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn unknown_dependencies_are_ignored() {
+        let graph = graph(&[("a", &["elsewhere"])]);
+        assert_eq!(layers(&graph).unwrap(), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn two_cycle() {
+        let graph = graph(&[("a", &["b"]), ("b", &["a"]), ("c", &[])]);
+        let err = layers(&graph).unwrap_err().to_string();
+        assert!(err.contains("a -> b -> a"), "{}", err);
+    }
+
+    #[test]
+    fn self_dependency() {
+        let graph = graph(&[("a", &["a"])]);
+        let err = layers(&graph).unwrap_err().to_string();
+        assert!(err.contains("a -> a"), "{}", err);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod ansi;
 pub mod daemon;
+pub mod deps;
 pub mod error;
 pub mod protocol;
@@ -0,0 +1,106 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The probe performed to check the health of a process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum HealthProbe {
+    /// Runs a command (in the process' environment), which must exit successfully.
+    Exec { cmd: Vec<String> },
+    /// Connects to a local TCP port, which must accept the connection.
+    Tcp { port: u16 },
+    /// Sends a GET request to a local HTTP endpoint, which must answer with a 2xx or 3xx status.
+    Http { port: u16, path: String },
+}
+
+impl Display for HealthProbe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HealthProbe::Exec { cmd } => write!(f, "exec {:?}", cmd),
+            HealthProbe::Tcp { port } => write!(f, "tcp :{}", port),
+            HealthProbe::Http { port, path } => write!(f, "http :{}{}", port, path),
+        }
+    }
+}
+
+/// The delay used by default between two consecutive health checks (in milliseconds).
+pub fn default_health_interval() -> u64 {
+    10_000
+}
+
+/// The time given by default to a single health check to succeed (in milliseconds).
+pub fn default_health_timeout() -> u64 {
+    5_000
+}
+
+/// The number of consecutive failed health checks after which a process is considered unhealthy, by default.
+pub fn default_health_retries() -> u32 {
+    3
+}
+
+/// The health check configuration of a process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthCheckSpec {
+    /// The probe to perform.
+    pub probe: HealthProbe,
+    /// The delay between two consecutive checks (in milliseconds).
+    #[serde(default = "default_health_interval")]
+    pub interval: u64,
+    /// The time given to a single check to succeed (in milliseconds).
+    #[serde(default = "default_health_timeout")]
+    pub timeout: u64,
+    /// The number of consecutive failed checks after which the process is considered unhealthy.
+    #[serde(default = "default_health_retries")]
+    pub retries: u32,
+    /// Whether to restart the process once it becomes unhealthy.
+    #[serde(default)]
+    pub restart: bool,
+}
+
+/// The health of a running process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthStatus {
+    /// No check succeeded yet, but not enough of them failed to consider the process unhealthy.
+    Starting,
+    /// The last check succeeded.
+    Healthy,
+    /// Too many consecutive checks failed.
+    Unhealthy,
+}
+
+impl Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HealthStatus::Starting => write!(f, "starting"),
+            HealthStatus::Healthy => write!(f, "healthy"),
+            HealthStatus::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
+/// Information about the health of a running process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthInfo {
+    pub status: HealthStatus,
+    /// The number of consecutive failed checks.
+    pub failures: u32,
+    /// When the last check was performed.
+    pub checked_at: Option<chrono::NaiveDateTime>,
+    /// The reason of the last failed check, if the last check failed.
+    pub error: Option<String>,
+}
+
+impl Default for HealthInfo {
+    fn default() -> Self {
+        HealthInfo {
+            status: HealthStatus::Starting,
+            failures: 0,
+            checked_at: None,
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// When a job runs.
+///
+/// It is written either as `once`, or as a cron expression (as in `*/5 * * * *` or `@daily`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum JobSchedule {
+    /// The job runs a single time, as soon as it gets started.
+    Once,
+    /// The job runs every time its cron expression matches.
+    Cron(String),
+}
+
+impl Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobSchedule::Once => write!(f, "once"),
+            JobSchedule::Cron(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl FromStr for JobSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err(String::from(
+                "empty job schedule (expected 'once' or a cron expression)",
+            )),
+            "once" => Ok(JobSchedule::Once),
+            expr => Ok(JobSchedule::Cron(expr.to_string())),
+        }
+    }
+}
+
+impl From<JobSchedule> for String {
+    fn from(schedule: JobSchedule) -> String {
+        schedule.to_string()
+    }
+}
+
+impl TryFrom<String> for JobSchedule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// What happens when a job is due to run while its previous run is still going.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlapPolicy {
+    /// The new run is skipped.
+    #[default]
+    Skip,
+    /// The new run starts once the previous one exits (at most one run is kept waiting).
+    Queue,
+    /// The previous run is stopped, to start the new one right away.
+    Kill,
+}
+
+impl Display for OverlapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverlapPolicy::Skip => write!(f, "skip"),
+            OverlapPolicy::Queue => write!(f, "queue"),
+            OverlapPolicy::Kill => write!(f, "kill"),
+        }
+    }
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(OverlapPolicy::Skip),
+            "queue" => Ok(OverlapPolicy::Queue),
+            "kill" => Ok(OverlapPolicy::Kill),
+            _ => Err(format!(
+                "unknown overlap policy '{}' (expected 'skip', 'queue' or 'kill')",
+                s
+            )),
+        }
+    }
+}
+
+/// The configuration of a process that runs as a job (on a schedule, or once), rather than continuously.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JobSpec {
+    pub schedule: JobSchedule,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+}
+
+impl Display for JobSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.schedule {
+            JobSchedule::Once => write!(f, "once"),
+            JobSchedule::Cron(ref expr) => write!(f, "cron '{}', {} overlaps", expr, self.overlap),
+        }
+    }
+}
+
+/// Information about the runs of a job.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JobInfo {
+    /// When the last run of the job started.
+    pub last_run: Option<chrono::NaiveDateTime>,
+    /// When the next run of the job is due (if it is scheduled).
+    pub next_run: Option<chrono::NaiveDateTime>,
+    /// The number of runs skipped so far, because the previous run was still going.
+    pub skipped: u32,
+    /// Whether a run is waiting for the current one to exit.
+    pub queued: bool,
+}
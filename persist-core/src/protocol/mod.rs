@@ -1,40 +1,670 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::{self, Display};
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use nix::sys::signal::Signal;
 use serde::{Deserialize, Serialize};
 
+mod health;
+mod job;
 mod request;
 mod response;
+mod structured;
 
+pub use self::health::*;
+pub use self::job::*;
 pub use self::request::*;
 pub use self::response::*;
+pub use self::structured::*;
 
 /// The status of a process.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum ProcessStatus {
     /// The process is running normally.
+    #[default]
     Running,
-    /// The process is stopped.
+    /// The process is stopped (it never ran, or got stopped on request).
     Stopped,
+    /// The process is a job waiting for its next scheduled run.
+    Scheduled,
+    /// The process exited on its own, successfully.
+    Exited,
+    /// The process exited on its own, unsuccessfully (non-zero exit code or killed by a signal).
+    Crashed,
+}
+
+impl Display for ProcessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Stopped => write!(f, "stopped"),
+            ProcessStatus::Scheduled => write!(f, "scheduled"),
+            ProcessStatus::Exited => write!(f, "exited"),
+            ProcessStatus::Crashed => write!(f, "crashed"),
+        }
+    }
+}
+
+/// Information about how a run of a process ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExitInfo {
+    /// The exit code of the process, if it exited normally.
+    pub code: Option<i32>,
+    /// The number of the signal that terminated the process, if it got killed by one.
+    pub signal: Option<i32>,
+    /// Whether the process got stopped on request (as opposed to exiting on its own).
+    pub stopped: bool,
+    /// When the process exited.
+    pub exited_at: chrono::NaiveDateTime,
+    /// How long the process ran for (in milliseconds).
+    pub duration: u64,
+}
+
+impl ExitInfo {
+    /// Whether the process exited successfully.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl Display for ExitInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "code {}", code),
+            (None, Some(signal)) => match Signal::try_from(signal) {
+                Ok(signal) => write!(f, "{}", signal),
+                Err(_) => write!(f, "signal {}", signal),
+            },
+            (None, None) => write!(f, "unknown"),
+        }
+    }
+}
+
+/// The policy used to decide whether a process should be restarted after it exited on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// The process is never restarted.
+    #[default]
+    Never,
+    /// The process is restarted only if it exited unsuccessfully (non-zero exit code or killed by a signal).
+    OnFailure,
+    /// The process is always restarted, regardless of how it exited.
+    Always,
+}
+
+impl Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RestartPolicy::Never => write!(f, "never"),
+            RestartPolicy::OnFailure => write!(f, "on-failure"),
+            RestartPolicy::Always => write!(f, "always"),
+        }
+    }
+}
+
+impl FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(RestartPolicy::Never),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "always" => Ok(RestartPolicy::Always),
+            _ => Err(format!(
+                "unknown restart policy '{}' (expected 'never', 'on-failure' or 'always')",
+                s
+            )),
+        }
+    }
 }
 
-impl Default for ProcessStatus {
+/// The restart configuration of a process.
+///
+/// Consecutive restarts are delayed using an exponential backoff,
+/// starting at `backoff` milliseconds and doubling on each attempt, up to `max_backoff` milliseconds.  
+/// A run lasting longer than `max_backoff` is considered stable and resets the retry counter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RestartSpec {
+    /// When to restart the process.
+    pub policy: RestartPolicy,
+    /// The maximum number of consecutive restart attempts (`None` means no limit).
+    pub max_retries: Option<u32>,
+    /// The delay before the first restart attempt (in milliseconds).
+    pub backoff: u64,
+    /// The upper bound for the delay between restart attempts (in milliseconds).
+    pub max_backoff: u64,
+}
+
+impl Default for RestartSpec {
     fn default() -> Self {
-        ProcessStatus::Running
+        RestartSpec {
+            policy: RestartPolicy::Never,
+            max_retries: None,
+            backoff: 1_000,
+            max_backoff: 60_000,
+        }
     }
 }
 
-impl Display for ProcessStatus {
+/// The rotation configuration of the log files of a process.
+///
+/// A log file gets rotated once it exceeds `max_size` or `max_age` (whichever comes first),
+/// by renaming it to `<file>.1` (shifting older ones to `<file>.2`, `<file>.3`, ...).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LogRotationSpec {
+    /// The size above which a log file gets rotated (in bytes).
+    pub max_size: Option<u64>,
+    /// The age above which a log file gets rotated (in milliseconds).
+    pub max_age: Option<u64>,
+    /// The number of rotated files to keep (older ones are deleted).
+    pub retain: u32,
+    /// Whether to compress rotated files (using gzip).
+    pub compress: bool,
+}
+
+impl LogRotationSpec {
+    /// Whether log files are rotated at all.
+    pub fn enabled(&self) -> bool {
+        self.max_size.is_some() || self.max_age.is_some()
+    }
+}
+
+impl Default for LogRotationSpec {
+    fn default() -> Self {
+        LogRotationSpec {
+            max_size: None,
+            max_age: None,
+            retain: 5,
+            compress: false,
+        }
+    }
+}
+
+/// How the lines of a multi-line log entry (like a stack trace) are recognized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum MultilineRule {
+    /// Lines starting with whitespace continue the previous entry.
+    Indent,
+    /// Only lines matching this regular expression start a new entry, the other ones continue the previous entry.
+    Start { pattern: String },
+}
+
+impl Display for MultilineRule {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ProcessStatus::Running => write!(f, "running"),
-            ProcessStatus::Stopped => write!(f, "stopped"),
+            MultilineRule::Indent => write!(f, "indent"),
+            MultilineRule::Start { pattern } => write!(f, "start /{}/", pattern),
+        }
+    }
+}
+
+/// The delay after which a multi-line log entry gets emitted if no more lines arrive for it, by default (in milliseconds).
+pub fn default_multiline_timeout() -> u64 {
+    500
+}
+
+/// The maximum number of lines grouped into a single log entry, by default.
+pub fn default_multiline_max_lines() -> usize {
+    500
+}
+
+/// The grouping of consecutive output lines into multi-line log entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MultilineSpec {
+    pub rule: MultilineRule,
+    /// The delay after which an entry gets emitted if no more lines arrive for it (in milliseconds).
+    #[serde(default = "default_multiline_timeout")]
+    pub timeout: u64,
+    /// The maximum number of lines grouped into a single entry.
+    #[serde(default = "default_multiline_max_lines")]
+    pub max_lines: usize,
+}
+
+/// The path of the local syslog socket, by default.
+pub fn default_syslog_socket() -> PathBuf {
+    PathBuf::from("/dev/log")
+}
+
+/// The syslog facility used by default.
+pub fn default_syslog_facility() -> String {
+    String::from("user")
+}
+
+/// The path of the journald native socket, by default.
+pub fn default_journald_socket() -> PathBuf {
+    PathBuf::from("/run/systemd/journal/socket")
+}
+
+/// Gets the code of a syslog facility from its name (as in `user`, `daemon` or `local0`).
+pub fn parse_syslog_facility(name: &str) -> Result<u8, String> {
+    let code = match name.to_ascii_lowercase().as_str() {
+        "kern" => 0,
+        "user" => 1,
+        "mail" => 2,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "lpr" => 6,
+        "news" => 7,
+        "uucp" => 8,
+        "cron" => 9,
+        "authpriv" => 10,
+        "ftp" => 11,
+        local => match local.strip_prefix("local").map(str::parse::<u8>) {
+            Some(Ok(index)) if index < 8 => 16 + index,
+            _ => return Err(format!("unknown syslog facility: '{}'", name)),
+        },
+    };
+
+    Ok(code)
+}
+
+/// An additional destination for the logs of a process (on top of its log files).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LogSink {
+    /// The local syslog daemon, through its Unix socket (using the RFC 5424 format).
+    #[serde(rename_all = "kebab-case")]
+    Syslog {
+        #[serde(default = "default_syslog_socket")]
+        socket: PathBuf,
+        #[serde(default = "default_syslog_facility")]
+        facility: String,
+        /// The application name to report (the name of the process, by default).
+        #[serde(default)]
+        tag: Option<String>,
+    },
+    /// The systemd journal, through its native socket protocol.
+    Journald {
+        #[serde(default = "default_journald_socket")]
+        socket: PathBuf,
+    },
+}
+
+impl Display for LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogSink::Syslog {
+                socket,
+                facility,
+                tag,
+            } => {
+                write!(f, "syslog ({}, facility {}", socket.display(), facility)?;
+                if let Some(tag) = tag {
+                    write!(f, ", tag {}", tag)?;
+                }
+                write!(f, ")")
+            }
+            LogSink::Journald { socket } => write!(f, "journald ({})", socket.display()),
+        }
+    }
+}
+
+/// How the ANSI escape sequences (colors, cursor movements, ...) output by a process are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnsiMode {
+    /// The sequences are kept everywhere.
+    #[default]
+    Keep,
+    /// The sequences are removed before the output gets written to disk or streamed.
+    Strip,
+    /// The sequences are removed from what gets written to disk, but kept for live streaming.
+    LiveOnly,
+}
+
+impl AnsiMode {
+    /// Whether the sequences are kept in the log files (and additional destinations).
+    pub fn keep_stored(&self) -> bool {
+        matches!(self, AnsiMode::Keep)
+    }
+
+    /// Whether the sequences are kept in the live stream.
+    pub fn keep_live(&self) -> bool {
+        matches!(self, AnsiMode::Keep | AnsiMode::LiveOnly)
+    }
+}
+
+impl Display for AnsiMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnsiMode::Keep => write!(f, "keep"),
+            AnsiMode::Strip => write!(f, "strip"),
+            AnsiMode::LiveOnly => write!(f, "live-only"),
         }
     }
 }
 
+impl FromStr for AnsiMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(AnsiMode::Keep),
+            "strip" => Ok(AnsiMode::Strip),
+            "live-only" => Ok(AnsiMode::LiveOnly),
+            _ => Err(format!(
+                "unknown ANSI mode '{}' (expected 'keep', 'strip' or 'live-only')",
+                s
+            )),
+        }
+    }
+}
+
+/// What a process waits for from one of its dependencies, before getting started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyCondition {
+    /// The dependency must be running.
+    #[default]
+    Started,
+    /// The dependency must be running and pass its health check.
+    Healthy,
+}
+
+impl Display for DependencyCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DependencyCondition::Started => write!(f, "started"),
+            DependencyCondition::Healthy => write!(f, "healthy"),
+        }
+    }
+}
+
+impl FromStr for DependencyCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "started" => Ok(DependencyCondition::Started),
+            "healthy" => Ok(DependencyCondition::Healthy),
+            _ => Err(format!(
+                "unknown dependency condition '{}' (expected 'started' or 'healthy')",
+                s
+            )),
+        }
+    }
+}
+
+/// The default time given to the dependencies of a process to meet their condition, in milliseconds.
+pub fn default_dependency_timeout() -> u64 {
+    60_000
+}
+
+/// A process that another process depends on, which is started before it and stopped after it.
+///
+/// It can be declared either in full, or as `NAME` (or `NAME:CONDITION`, as in `db:healthy`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", try_from = "DependencyDecl")]
+pub struct Dependency {
+    pub name: String,
+    pub condition: DependencyCondition,
+    /// How long to wait for the condition to be met before giving up on starting the process (in milliseconds).
+    pub timeout: u64,
+}
+
+/// The accepted ways of declaring a dependency.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DependencyDecl {
+    Short(String),
+    #[serde(rename_all = "kebab-case")]
+    Full {
+        name: String,
+        #[serde(default)]
+        condition: DependencyCondition,
+        #[serde(default = "default_dependency_timeout")]
+        timeout: u64,
+    },
+}
+
+impl TryFrom<DependencyDecl> for Dependency {
+    type Error = String;
+
+    fn try_from(decl: DependencyDecl) -> Result<Self, Self::Error> {
+        match decl {
+            DependencyDecl::Short(decl) => decl.parse(),
+            DependencyDecl::Full {
+                name,
+                condition,
+                timeout,
+            } => Ok(Dependency {
+                name,
+                condition,
+                timeout,
+            }),
+        }
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.condition {
+            DependencyCondition::Started => write!(f, "{}", self.name),
+            DependencyCondition::Healthy => write!(f, "{} (healthy)", self.name),
+        }
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //? names of replicas contain a colon as well (as in `worker:0`), but never end with a condition.
+        let (name, condition) = match s.rsplit_once(':') {
+            Some((name, condition)) if condition.parse::<usize>().is_err() => {
+                (name, condition.parse()?)
+            }
+            _ => (s, DependencyCondition::default()),
+        };
+        if name.is_empty() {
+            return Err(format!(
+                "invalid dependency '{}' (expected NAME[:CONDITION])",
+                s
+            ));
+        }
+
+        Ok(Dependency {
+            condition,
+            name: name.to_string(),
+            timeout: default_dependency_timeout(),
+        })
+    }
+}
+
+pub fn default_pty_rows() -> u16 {
+    24
+}
+
+pub fn default_pty_cols() -> u16 {
+    80
+}
+
+/// The pseudo-terminal that a process runs in, instead of having its output captured through pipes.
+///
+/// Its stdout and stderr cannot be told apart anymore, so all of its output is captured as stdout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PtySpec {
+    /// The initial number of rows of the terminal window.
+    #[serde(default = "default_pty_rows")]
+    pub rows: u16,
+    /// The initial number of columns of the terminal window.
+    #[serde(default = "default_pty_cols")]
+    pub cols: u16,
+}
+
+impl Default for PtySpec {
+    fn default() -> Self {
+        Self {
+            rows: default_pty_rows(),
+            cols: default_pty_cols(),
+        }
+    }
+}
+
+impl Display for PtySpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.cols, self.rows)
+    }
+}
+
+impl FromStr for PtySpec {
+    type Err = String;
+
+    /// Parses a window size, formatted as `COLSxROWS` (as in `80x24`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid window size '{}' (expected COLSxROWS)", s);
+        let (cols, rows) = s.split_once('x').ok_or_else(invalid)?;
+        let cols = cols.parse().map_err(|_| invalid())?;
+        let rows = rows.parse().map_err(|_| invalid())?;
+
+        Ok(Self { rows, cols })
+    }
+}
+
+/// The delay during which file changes are gathered before restarting a process, by default (in milliseconds).
+pub fn default_watch_debounce() -> u64 {
+    500
+}
+
+/// The files to watch, to restart a process whenever some of them change.
+///
+/// Directories are watched recursively, and globs are matched against paths relative to the process' working directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WatchSpec {
+    /// The files and directories to watch (relative to the process' working directory).
+    pub paths: Vec<PathBuf>,
+    /// Only changes to files matching one of these globs restart the process (all files do, if there are none).
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Changes to files matching one of these globs are ignored (like `target/**` or `**/*.swp`).
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// The delay without further changes to wait for, before restarting the process (in milliseconds).
+    #[serde(default = "default_watch_debounce")]
+    pub debounce: u64,
+}
+
+impl Display for WatchSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let paths: Vec<String> = self
+            .paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        write!(f, "{} (", paths.join(", "))?;
+        if !self.include.is_empty() {
+            write!(f, "including {}, ", self.include.join(", "))?;
+        }
+        if !self.ignore.is_empty() {
+            write!(f, "ignoring {}, ", self.ignore.join(", "))?;
+        }
+        write!(f, "{}ms debounce)", self.debounce)
+    }
+}
+
+/// The file changes that last triggered a restart of a process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WatchTrigger {
+    /// The changed files (relative to the process' working directory, when within it).
+    pub paths: Vec<PathBuf>,
+    pub changed_at: chrono::NaiveDateTime,
+}
+
+impl Display for WatchTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        //? only the first few paths are listed, since a single build can touch many files.
+        let shown: Vec<String> = self
+            .paths
+            .iter()
+            .take(3)
+            .map(|path| path.display().to_string())
+            .collect();
+        write!(f, "{}", shown.join(", "))?;
+        if self.paths.len() > shown.len() {
+            write!(f, " and {} more", self.paths.len() - shown.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// The environment variable holding the port of each instance, by default.
+pub fn default_port_env() -> String {
+    String::from("PORT")
+}
+
+/// The replicas to run of a process, each of them being managed as a process of its own.
+///
+/// The replicas are named after the process and their index (as in `worker:0`, `worker:1`, ...),
+/// and get their index in the `PERSIST_INSTANCE` environment variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstancesSpec {
+    /// The number of replicas.
+    pub count: usize,
+    /// The port of the first replica, offset by the index of each other replica (as in `3000`, `3001`, ...).
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// The environment variable to pass the port of each replica in.
+    #[serde(default = "default_port_env")]
+    pub port_env: String,
+}
+
+impl InstancesSpec {
+    /// Gets the port of one of the replicas, if they get one.
+    pub fn port_of(&self, index: usize) -> Option<u16> {
+        let offset = u16::try_from(index).ok()?;
+        self.port?.checked_add(offset)
+    }
+}
+
+/// The number of log lines buffered by default for live subscribers, per output stream.
+pub fn default_log_buffer() -> usize {
+    1024
+}
+
+/// The largest number of log lines that can be buffered for live subscribers, per output stream
+/// (the buffers get allocated upfront).
+pub const MAX_LOG_BUFFER: usize = 65_536;
+
+/// The signal sent by default to stop a process.
+pub fn default_stop_signal() -> String {
+    String::from("SIGTERM")
+}
+
+/// The time given by default to a process to exit after being signaled to stop (in milliseconds).
+pub fn default_stop_timeout() -> u64 {
+    10_000
+}
+
+/// Parses a signal from its name (like `SIGTERM` or `term`) or its number (like `15`).
+pub fn parse_signal(name: &str) -> Result<Signal, String> {
+    if let Ok(number) = name.parse::<i32>() {
+        return Signal::try_from(number).map_err(|_| format!("unknown signal number '{}'", name));
+    }
+
+    let upper = name.to_ascii_uppercase();
+    let full = if upper.starts_with("SIG") {
+        upper
+    } else {
+        format!("SIG{}", upper)
+    };
+
+    Signal::from_str(full.as_str()).map_err(|_| format!("unknown signal '{}'", name))
+}
+
 /// A process specification.
 ///
 /// It is a complete description of a process' environment and configuration.  
@@ -47,12 +677,77 @@ pub struct ProcessSpec {
     pub env: HashMap<String, String>,
     #[serde(default)]
     pub status: ProcessStatus,
+    #[serde(default)]
+    pub restart: RestartSpec,
+    /// The signal sent to the process to stop it.
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    /// The time given to the process to exit after being signaled to stop,
+    /// before getting forcibly killed (in milliseconds).
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: u64,
+    /// The health check to periodically perform while the process is running.
+    #[serde(default)]
+    pub health_check: Option<HealthCheckSpec>,
+    #[serde(default)]
+    pub log_rotation: LogRotationSpec,
+    /// The number of log lines buffered for live subscribers (per output stream),
+    /// before the slowest of them start lagging behind.
+    #[serde(default = "default_log_buffer")]
+    pub log_buffer: usize,
+    /// The grouping of consecutive output lines into multi-line log entries.
+    #[serde(default)]
+    pub multiline: Option<MultilineSpec>,
+    /// The format of the lines that the process outputs.
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// The additional destinations of the process' logs.
+    #[serde(default)]
+    pub log_sinks: Vec<LogSink>,
+    /// How the ANSI escape sequences output by the process are handled.
+    #[serde(default)]
+    pub ansi: AnsiMode,
+    /// Whether the process' stdin is kept open by the daemon, to send input to it (instead of being empty).
+    #[serde(default)]
+    pub stdin: bool,
+    /// The pseudo-terminal to run the process in (if any).
+    #[serde(default)]
+    pub pty: Option<PtySpec>,
+    /// The processes to start before this one (and to stop after it).
+    #[serde(default)]
+    pub depends_on: Vec<Dependency>,
+    /// The schedule to run the process on, if it runs as a job (rather than continuously).
+    #[serde(default)]
+    pub job: Option<JobSpec>,
+    /// The files to watch, to restart the process whenever they change.
+    #[serde(default)]
+    pub watch: Option<WatchSpec>,
+    /// The replicas to run of the process (if it runs as several instances).
+    #[serde(default)]
+    pub instances: Option<InstancesSpec>,
+    /// The index of the replica that this specification describes (if it is one).
+    #[serde(default)]
+    pub instance: Option<usize>,
     pub pid_path: PathBuf,
     pub stdout_path: PathBuf,
     pub stderr_path: PathBuf,
     pub created_at: chrono::NaiveDateTime,
 }
 
+impl ProcessSpec {
+    /// Gets the name of the group of replicas that the process belongs to (its own name, if it is not a replica).
+    pub fn group(&self) -> &str {
+        let Some(index) = self.instance else {
+            return self.name.as_str();
+        };
+
+        let suffix = format!(":{}", index);
+        self.name
+            .strip_suffix(suffix.as_str())
+            .unwrap_or(&self.name)
+    }
+}
+
 /// Information about a current state of a process.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProcessInfo {
@@ -62,6 +757,33 @@ pub struct ProcessInfo {
     pub env: HashMap<String, String>,
     pub pid: Option<usize>,
     pub status: ProcessStatus,
+    pub restart: RestartSpec,
+    /// The number of consecutive automatic restarts performed so far.
+    pub restarts: u32,
+    /// How the last run of the process ended.
+    pub exit: Option<ExitInfo>,
+    pub stop_signal: String,
+    pub stop_timeout: u64,
+    pub health_check: Option<HealthCheckSpec>,
+    /// The health of the process (if it is running and has a health check).
+    pub health: Option<HealthInfo>,
+    pub log_rotation: LogRotationSpec,
+    pub log_buffer: usize,
+    pub multiline: Option<MultilineSpec>,
+    pub log_format: LogFormat,
+    pub log_sinks: Vec<LogSink>,
+    pub ansi: AnsiMode,
+    pub stdin: bool,
+    pub pty: Option<PtySpec>,
+    pub depends_on: Vec<Dependency>,
+    pub job: Option<JobSpec>,
+    /// Information about the runs of the process (if it runs as a job).
+    pub runs: Option<JobInfo>,
+    pub watch: Option<WatchSpec>,
+    /// The file changes that last triggered a restart of the process.
+    pub last_change: Option<WatchTrigger>,
+    pub instances: Option<InstancesSpec>,
+    pub instance: Option<usize>,
     pub pid_path: PathBuf,
     pub stdout_path: PathBuf,
     pub stderr_path: PathBuf,
@@ -76,6 +798,23 @@ impl From<ProcessInfo> for ProcessSpec {
             cwd: info.cwd,
             env: info.env,
             status: info.status,
+            restart: info.restart,
+            stop_signal: info.stop_signal,
+            stop_timeout: info.stop_timeout,
+            health_check: info.health_check,
+            log_rotation: info.log_rotation,
+            log_buffer: info.log_buffer,
+            multiline: info.multiline,
+            log_format: info.log_format,
+            log_sinks: info.log_sinks,
+            ansi: info.ansi,
+            stdin: info.stdin,
+            pty: info.pty,
+            depends_on: info.depends_on,
+            job: info.job,
+            watch: info.watch,
+            instances: info.instances,
+            instance: info.instance,
             pid_path: info.pid_path,
             stdout_path: info.stdout_path,
             stderr_path: info.stderr_path,
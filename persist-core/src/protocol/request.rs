@@ -1,9 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-use crate::protocol::{LogStreamSource, ProcessSpec, ProcessStatus};
+use crate::protocol::{
+    default_log_buffer, default_stop_signal, default_stop_timeout, AnsiMode, Dependency,
+    HealthCheckSpec, InstancesSpec, JobSpec, LogFormat, LogLevel, LogRotationSpec, LogSink,
+    LogStreamSource, MultilineSpec, ProcessSpec, ProcessStatus, PtySpec, RestartSpec, WatchSpec,
+};
 
 /// A request to start managing a new process.
 ///
@@ -15,6 +19,38 @@ pub struct StartRequest {
     pub cwd: PathBuf,
     pub env: HashMap<String, String>,
     pub status: ProcessStatus,
+    #[serde(default)]
+    pub restart: RestartSpec,
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: u64,
+    #[serde(default)]
+    pub health_check: Option<HealthCheckSpec>,
+    #[serde(default)]
+    pub log_rotation: LogRotationSpec,
+    #[serde(default = "default_log_buffer")]
+    pub log_buffer: usize,
+    #[serde(default)]
+    pub multiline: Option<MultilineSpec>,
+    #[serde(default)]
+    pub log_format: LogFormat,
+    #[serde(default)]
+    pub log_sinks: Vec<LogSink>,
+    #[serde(default)]
+    pub ansi: AnsiMode,
+    #[serde(default)]
+    pub stdin: bool,
+    #[serde(default)]
+    pub pty: Option<PtySpec>,
+    #[serde(default)]
+    pub depends_on: Vec<Dependency>,
+    #[serde(default)]
+    pub job: Option<JobSpec>,
+    #[serde(default)]
+    pub watch: Option<WatchSpec>,
+    #[serde(default)]
+    pub instances: Option<InstancesSpec>,
 }
 
 /// A request to start managing a new process.
@@ -28,6 +64,12 @@ pub struct ListRequest {
 #[serde(rename_all = "kebab-case")]
 pub struct StopRequest {
     pub filters: Option<Vec<String>>,
+    /// Overrides the signal sent to the processes to stop them.
+    #[serde(default)]
+    pub signal: Option<String>,
+    /// Overrides the time given to the processes to exit before getting forcibly killed (in milliseconds).
+    #[serde(default)]
+    pub timeout: Option<u64>,
 }
 
 /// A request to restart managed processes.
@@ -61,14 +103,56 @@ pub struct RestoreRequest {
     pub specs: Vec<ProcessSpec>,
 }
 
-/// A request to restore processes from a previously generated dump.
+/// A search through log entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogSearch {
+    /// The pattern to search for (a regular expression, unless `literal` is set).
+    pub pattern: String,
+    /// Whether the pattern is a plain substring, rather than a regular expression.
+    #[serde(default)]
+    pub literal: bool,
+    /// Whether to match the pattern case-insensitively.
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// The number of entries to also include before each matching entry.
+    #[serde(default)]
+    pub before: usize,
+    /// The number of entries to also include after each matching entry.
+    #[serde(default)]
+    pub after: usize,
+}
+
+/// A request to get (and optionally stream) the logs of managed processes.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LogsRequest {
     pub filters: Option<Vec<String>>,
     pub source_filter: Option<LogStreamSource>,
     pub stream: bool,
     pub lines: usize,
+    /// Only get log entries captured at or after this time.
+    #[serde(default)]
+    pub since: Option<chrono::DateTime<chrono::Local>>,
+    /// Only get log entries captured at or before this time.
+    #[serde(default)]
+    pub until: Option<chrono::DateTime<chrono::Local>>,
+    /// Recover the entries skipped while lagging behind a live stream from the log files,
+    /// instead of only reporting a gap.
+    #[serde(default)]
+    pub resume_on_lag: bool,
+    /// Only get the entries matching this search (along with their context),
+    /// in which case `lines` is the number of previous matches to get.
+    #[serde(default)]
+    pub search: Option<LogSearch>,
+    /// Only get the structured entries of at least this level.
+    #[serde(default)]
+    pub level: Option<LogLevel>,
+    /// Only get the structured entries having these values for these fields
+    /// (nested fields being separated by dots, as in `http.status`).
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
 }
+
 /// A request to prune logs and pid files of unmanaged and/or stopped processes.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PruneRequest {
@@ -76,12 +160,42 @@ pub struct PruneRequest {
     pub stopped: bool,
 }
 
+/// A request to write some input to the stdin of a running process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRequest {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// A request to change the window size of the pseudo-terminal of a running process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResizeRequest {
+    pub name: String,
+    pub rows: u16,
+    pub cols: u16,
+}
+
+/// A request to change the number of replicas of a process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScaleRequest {
+    pub name: String,
+    pub count: usize,
+}
+
+/// A request to shut the daemon down.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KillRequest {
+    /// Leave the managed processes running, so that a future daemon can re-adopt them.
+    #[serde(default)]
+    pub keep_processes: bool,
+}
+
 /// A request (from a client to the daemon).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data", rename_all = "kebab-case")]
 pub enum Request {
     List(ListRequest),
-    Start(StartRequest),
+    Start(Box<StartRequest>),
     Stop(StopRequest),
     Restart(RestartRequest),
     Info(InfoRequest),
@@ -90,6 +204,9 @@ pub enum Request {
     Restore(RestoreRequest),
     Logs(LogsRequest),
     Prune(PruneRequest),
+    Input(InputRequest),
+    Resize(ResizeRequest),
+    Scale(ScaleRequest),
     Version,
-    Kill,
+    Kill(KillRequest),
 }
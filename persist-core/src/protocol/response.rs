@@ -1,6 +1,11 @@
+use std::fmt::{self, Display};
+
 use serde::{Deserialize, Serialize};
 
-use crate::protocol::{LogStreamSource, ProcessInfo, ProcessSpec, ProcessStatus};
+use crate::protocol::{
+    ExitInfo, HealthStatus, JobInfo, LogStreamSource, ProcessInfo, ProcessSpec, ProcessStatus,
+    StructuredLog, WatchTrigger,
+};
 
 /// A response to list information and metrics about managed processes.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -8,6 +13,14 @@ pub struct ListResponse {
     pub name: String,
     pub pid: Option<usize>,
     pub status: ProcessStatus,
+    /// The number of consecutive automatic restarts performed so far.
+    pub restarts: u32,
+    /// How the last run of the process ended.
+    pub exit: Option<ExitInfo>,
+    /// The health of the process (if it is running and has a health check).
+    pub health: Option<HealthStatus>,
+    /// Information about the runs of the process (if it runs as a job).
+    pub job: Option<JobInfo>,
     pub cpu_usage: u32,
     pub mem_usage: u32,
 }
@@ -61,12 +74,31 @@ pub struct RestoreResponse {
     pub error: Option<String>,
 }
 
+/// A response to change the number of replicas of a process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScaleResponse {
+    pub name: String,
+    /// The names of the replicas that got added.
+    pub created: Vec<String>,
+    /// The names of the replicas that got removed.
+    pub deleted: Vec<String>,
+}
+
 /// A response to get version information about the daemon.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VersionResponse {
     pub version: String,
 }
 
+/// A response to shut the daemon down.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KillResponse {
+    /// The processes that got stopped.
+    pub stopped: Vec<StopResponse>,
+    /// The names of the processes that were left running.
+    pub kept: Vec<String>,
+}
+
 /// A log entry.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LogEntry {
@@ -74,8 +106,77 @@ pub struct LogEntry {
     pub name: String,
     /// Source stream of this log entry (stdout or stderr).
     pub source: LogStreamSource,
+    /// When the daemon captured this log entry (unknown for lines captured by older versions).
+    #[serde(default)]
+    pub timestamp: Option<chrono::DateTime<chrono::Local>>,
+    /// Whether this entry is only included as context around an entry matching a search.
+    #[serde(default)]
+    pub context: bool,
     /// The actual log message.
     pub msg: String,
+    /// The parsed contents of the message, if the process outputs JSON lines (and it is one).
+    #[serde(default)]
+    pub structured: Option<StructuredLog>,
+}
+
+/// A gap in a stream of log entries, because the client could not keep up with the process' output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogGap {
+    /// Name of the originating process.
+    pub name: String,
+    /// Source stream of the skipped log entries (stdout or stderr).
+    pub source: LogStreamSource,
+    /// The number of skipped log entries.
+    pub skipped: u64,
+}
+
+/// A change in the lifecycle of a process, reported inline with its logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+pub enum LifecycleEvent {
+    /// The process got started.
+    Started { pid: usize },
+    /// The process got started again (on request, or automatically after exiting).
+    Restarted { pid: usize },
+    /// The process, left running by a previous daemon, got taken back.
+    Adopted { pid: usize },
+    /// The process exited on its own.
+    Exited(ExitInfo),
+    /// Some of the watched files changed, so the process is getting restarted.
+    Changed(WatchTrigger),
+    /// The process got stopped on request.
+    Stopped,
+    /// A scheduled run of the job got skipped, because the previous one was still going.
+    Skipped,
+    /// The process stopped being managed.
+    Deleted,
+}
+
+impl Display for LifecycleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LifecycleEvent::Started { pid } => write!(f, "started, pid {}", pid),
+            LifecycleEvent::Restarted { pid } => write!(f, "restarted, pid {}", pid),
+            LifecycleEvent::Adopted { pid } => write!(f, "adopted, pid {}", pid),
+            LifecycleEvent::Exited(exit) => write!(f, "exited ({})", exit),
+            LifecycleEvent::Changed(trigger) => {
+                write!(f, "files changed ({}), restarting", trigger)
+            }
+            LifecycleEvent::Stopped => write!(f, "stopped"),
+            LifecycleEvent::Skipped => write!(f, "run skipped, previous one still going"),
+            LifecycleEvent::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+/// A lifecycle event of a followed process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEvent {
+    /// Name of the originating process.
+    pub name: String,
+    /// When the event occurred.
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub event: LifecycleEvent,
 }
 
 /// A response for log entries (from the daemon to a client).
@@ -84,6 +185,8 @@ pub struct LogEntry {
 pub enum LogsResponse {
     Subscribed,
     Entry(LogEntry),
+    Gap(LogGap),
+    Event(LogEvent),
     Unsubscribed,
 }
 
@@ -109,5 +212,9 @@ pub enum Response {
     Version(VersionResponse),
     Logs(LogsResponse),
     Prune(PruneResponse),
+    Input,
+    Resize,
+    Scale(ScaleResponse),
+    Killed(KillResponse),
     Error(String),
 }
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The format of the lines that a process outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Plain text lines.
+    #[default]
+    Text,
+    /// JSON objects (one per line), from which the level, message and timestamp get extracted.
+    Json,
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format: '{}'", s)),
+        }
+    }
+}
+
+/// The severity level of a structured log entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    /// Gets the level corresponding to a numeric level (as used by `bunyan` or `pino`).
+    pub fn from_number(number: u64) -> Self {
+        match number {
+            0..=10 => LogLevel::Trace,
+            11..=20 => LogLevel::Debug,
+            21..=30 => LogLevel::Info,
+            31..=40 => LogLevel::Warn,
+            41..=50 => LogLevel::Error,
+            _ => LogLevel::Fatal,
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogLevel::Trace => write!(f, "trace"),
+            LogLevel::Debug => write!(f, "debug"),
+            LogLevel::Info => write!(f, "info"),
+            LogLevel::Warn => write!(f, "warn"),
+            LogLevel::Error => write!(f, "error"),
+            LogLevel::Fatal => write!(f, "fatal"),
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" | "information" | "notice" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" | "err" => Ok(LogLevel::Error),
+            "fatal" | "critical" | "crit" | "panic" | "emerg" | "alert" => Ok(LogLevel::Fatal),
+            _ => Err(format!("unknown log level: '{}'", s)),
+        }
+    }
+}
+
+/// The contents of a log entry that got parsed as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructuredLog {
+    /// The severity level of the entry (if it has a recognized one).
+    pub level: Option<LogLevel>,
+    /// The message of the entry.
+    pub message: Option<String>,
+    /// The timestamp that the process gave to the entry (as-is).
+    pub time: Option<String>,
+    /// The remaining fields of the entry.
+    pub fields: BTreeMap<String, json::Value>,
+}
@@ -1,8 +1,38 @@
+use std::borrow::Cow;
+
 use bytes::BytesMut;
+use chrono::{DateTime, Local, SecondsFormat};
 use tokio_util::codec::Decoder;
 
 use persist_core::error::Error;
 
+/// Formats a captured line for storage in a log file, prefixed by the time at which it got captured.
+///
+/// The lines of multi-line entries are separated by carriage returns instead (which never appear within
+/// captured lines), so that every entry is stored as a single line of the log file.
+pub fn encode_line(timestamp: &DateTime<Local>, msg: &str) -> String {
+    let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::Micros, false);
+    format!("{} {}", timestamp, msg.replace('\n', "\r"))
+}
+
+/// Recovers a line stored in a log file, along with the time at which it got captured.
+///
+/// Lines written before timestamps got stored are returned as-is, without a timestamp.
+pub fn decode_line(line: &str) -> (Option<DateTime<Local>>, Cow<'_, str>) {
+    let parsed = line.split_once(' ').and_then(|(timestamp, msg)| {
+        let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?;
+        Some((timestamp.with_timezone(&Local), msg))
+    });
+
+    match parsed {
+        Some((timestamp, msg)) if msg.contains('\r') => {
+            (Some(timestamp), Cow::Owned(msg.replace('\r', "\n")))
+        }
+        Some((timestamp, msg)) => (Some(timestamp), Cow::Borrowed(msg)),
+        None => (None, Cow::Borrowed(line)),
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct LogDecoder {
     next_index: usize,
@@ -44,7 +74,9 @@ impl Decoder for LogDecoder {
             .map(|index| {
                 self.next_index = 0;
                 let line = src.split_to(index + 1);
+                //? a CRLF line still ends with its CR at this point.
                 let line = &line[..line.len() - 1];
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
                 let line = String::from_utf8_lossy(line).into_owned();
                 Ok(line)
             })
@@ -68,3 +100,86 @@ impl Decoder for LogDecoder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Decodes all the lines of a stream, as if it got received in the given chunks.
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<String> {
+        let mut decoder = LogDecoder::new();
+        let mut buffer = BytesMut::new();
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            buffer.extend_from_slice(chunk);
+            while let Some(line) = decoder.decode(&mut buffer).unwrap() {
+                lines.push(line);
+            }
+        }
+        lines.extend(decoder.decode_eof(&mut buffer).unwrap());
+        lines
+    }
+
+    #[test]
+    fn stored_lines_round_trip() {
+        let timestamp = Local.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        let line = encode_line(&timestamp, "hello world");
+        assert!(line.ends_with(" hello world"), "{}", line);
+
+        let (decoded, msg) = decode_line(&line);
+        assert_eq!(decoded, Some(timestamp));
+        assert_eq!(msg, "hello world");
+    }
+
+    #[test]
+    fn multiline_entries_are_stored_as_single_lines() {
+        let timestamp = Local::now();
+        let line = encode_line(&timestamp, "error\n  at here");
+        assert!(!line.contains('\n'));
+
+        let (_, msg) = decode_line(&line);
+        assert_eq!(msg, "error\n  at here");
+    }
+
+    #[test]
+    fn lines_without_timestamps() {
+        assert_eq!(
+            decode_line("legacy line"),
+            (None, Cow::Borrowed("legacy line"))
+        );
+        assert_eq!(
+            decode_line("not-a-date line"),
+            (None, Cow::Borrowed("not-a-date line"))
+        );
+        assert_eq!(decode_line(""), (None, Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn line_endings() {
+        let lines = decode_chunks(&[b"lf\ncrlf\r\ncr\rlast"]);
+        assert_eq!(lines, vec!["lf", "crlf", "cr", "last"]);
+    }
+
+    #[test]
+    fn crlf_split_across_chunks() {
+        let lines = decode_chunks(&[b"first\r", b"\nsecond\r", b"\n"]);
+        assert_eq!(lines, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn unterminated_lines_wait_for_more() {
+        let lines = decode_chunks(&[b"par", b"tial", b" line\n"]);
+        assert_eq!(lines, vec!["partial line"]);
+
+        let lines = decode_chunks(&[b"trailing cr\r"]);
+        assert_eq!(lines, vec!["trailing cr"]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let lines = decode_chunks(&[b"in\xffvalid\n"]);
+        assert_eq!(lines, vec!["in\u{fffd}valid"]);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use persist_core::error::Error;
+use persist_core::protocol::{Dependency, DependencyCondition, HealthStatus, ProcessSpec};
+
+use crate::server::State;
+
+pub use persist_core::deps::{layers, order};
+
+/// The delay between two checks of whether the dependencies of a process meet their conditions.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Gets the processes that a dependency refers to, among the given ones.
+///
+/// A dependency on a process running as several replicas refers to every one of them.
+fn resolve(specs: &[&ProcessSpec], name: &str) -> Vec<String> {
+    if specs.iter().any(|spec| spec.name == name) {
+        return vec![name.to_string()];
+    }
+
+    let members: Vec<String> = specs
+        .iter()
+        .filter(|spec| spec.instance.is_some() && spec.group() == name)
+        .map(|spec| spec.name.clone())
+        .collect();
+    match members.is_empty() {
+        true => vec![name.to_string()],
+        false => members,
+    }
+}
+
+/// Builds the dependency graph of the given processes, by name.
+pub fn graph<'a>(
+    specs: impl IntoIterator<Item = &'a ProcessSpec>,
+) -> BTreeMap<String, Vec<String>> {
+    let specs: Vec<&ProcessSpec> = specs.into_iter().collect();
+    specs
+        .iter()
+        .map(|spec| {
+            let deps = spec
+                .depends_on
+                .iter()
+                .flat_map(|dep| resolve(&specs, dep.name.as_str()))
+                .collect();
+            (spec.name.clone(), deps)
+        })
+        .collect()
+}
+
+/// Sorts the given process specifications into layers, such that every process comes after the ones it depends on.
+///
+/// Dependencies on other processes are left out, and cycles (which the daemon prevents) are ignored.
+pub fn spec_layers(mut specs: Vec<ProcessSpec>) -> Vec<Vec<ProcessSpec>> {
+    let graph = graph(specs.iter());
+    let names = layers(&graph).unwrap_or_else(|_| vec![graph.into_keys().collect()]);
+
+    let mut sorted = Vec::with_capacity(names.len());
+    for layer in names {
+        let (current, rest) = specs
+            .into_iter()
+            .partition::<Vec<_>, _>(|spec| layer.contains(&spec.name));
+        specs = rest;
+        sorted.push(current);
+    }
+
+    sorted
+}
+
+/// Sorts the given managed processes into layers, such that every process comes after the ones it depends on.
+///
+/// Unknown processes come first (to get reported as such), and cycles (which the daemon prevents) are ignored.
+pub async fn managed_layers(state: &State, names: Vec<String>) -> Vec<Vec<String>> {
+    let graph = state
+        .with_handles(|handles| {
+            let specs = names
+                .iter()
+                .filter_map(|name| handles.get(name))
+                .map(|handle| handle.spec());
+            graph(specs)
+        })
+        .await;
+
+    let unknown: Vec<String> = names
+        .iter()
+        .filter(|name| !graph.contains_key(*name))
+        .cloned()
+        .collect();
+    let sorted = layers(&graph).unwrap_or_else(|_| vec![graph.into_keys().collect()]);
+
+    std::iter::once(unknown)
+        .filter(|unknown| !unknown.is_empty())
+        .chain(sorted)
+        .collect()
+}
+
+/// The state of a dependency, as far as its condition is concerned.
+struct Probe {
+    running: bool,
+    /// Whether it is about to be restarted (by its restart policy).
+    restarting: bool,
+    has_health_check: bool,
+    health: Option<HealthStatus>,
+}
+
+/// Waits for the dependencies of a process to meet their conditions, before it gets started.
+///
+/// Dependencies that are about to be restarted are waited for, but the ones that are stopped are not.
+/// A dependency on a process running as several replicas waits for every one of them.
+pub async fn wait(state: &State, spec: &ProcessSpec) -> Result<(), Error> {
+    for dependency in spec.depends_on.iter() {
+        let deadline = Instant::now() + Duration::from_millis(dependency.timeout);
+        for name in state.resolve(vec![dependency.name.clone()]).await {
+            wait_for(state, spec, dependency, name.as_str(), deadline).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits for a single process to meet the condition of a dependency, until the given deadline.
+async fn wait_for(
+    state: &State,
+    spec: &ProcessSpec,
+    dependency: &Dependency,
+    name: &str,
+    deadline: Instant,
+) -> Result<(), Error> {
+    loop {
+        let probe = state
+            .with_handle(name, |handle| Probe {
+                running: handle.pid().is_some(),
+                restarting: handle.pending_restart.is_some(),
+                has_health_check: handle.spec().health_check.is_some(),
+                health: handle.health().map(|health| health.status),
+            })
+            .await
+            .map_err(|_| {
+                format!(
+                    "'{}' depends on '{}', which is not managed",
+                    spec.name, name
+                )
+            })?;
+
+        if !probe.running && !probe.restarting {
+            return Err(Error::from(format!(
+                "'{}' depends on '{}', which is not running",
+                spec.name, name
+            )));
+        }
+
+        let met = match dependency.condition {
+            DependencyCondition::Started => probe.running,
+            DependencyCondition::Healthy if !probe.has_health_check => {
+                return Err(Error::from(format!(
+                    "'{}' waits for '{}' to be healthy, but it has no health check",
+                    spec.name, name
+                )));
+            }
+            DependencyCondition::Healthy => {
+                probe.running && probe.health == Some(HealthStatus::Healthy)
+            }
+        };
+        if met {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(Error::from(format!(
+                "timed out waiting for '{}' to be {} (needed by '{}')",
+                name, dependency.condition, spec.name
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, depends_on: &[&str]) -> ProcessSpec {
+        json::from_value(json::json!({
+            "name": name,
+            "cmd": ["true"],
+            "cwd": "/",
+            "env": {},
+            "depends_on": depends_on,
+            "pid_path": "",
+            "stdout_path": "",
+            "stderr_path": "",
+            "created_at": "2024-01-01T00:00:00",
+        }))
+        .unwrap()
+    }
+
+    fn names(layers: Vec<Vec<ProcessSpec>>) -> Vec<Vec<String>> {
+        layers
+            .into_iter()
+            .map(|layer| layer.into_iter().map(|spec| spec.name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn chain_of_three() {
+        let specs = vec![spec("web", &["api"]), spec("db", &[]), spec("api", &["db"])];
+        let layers = names(spec_layers(specs));
+        assert_eq!(layers, vec![vec!["db"], vec!["api"], vec!["web"]]);
+    }
+
+    #[test]
+    fn dependencies_on_other_processes_are_left_out() {
+        //? as when resurrecting, where the processes without dependencies are started right away.
+        let specs = vec![spec("web", &["api"]), spec("api", &["db"])];
+        let layers = names(spec_layers(specs));
+        assert_eq!(layers, vec![vec!["api"], vec!["web"]]);
+    }
+
+    #[test]
+    fn replicas() {
+        let mut first = spec("worker:0", &["db"]);
+        first.instance = Some(0);
+        let mut second = spec("worker:1", &["db"]);
+        second.instance = Some(1);
+        let specs = vec![spec("web", &["worker"]), first, second, spec("db", &[])];
+        let layers = names(spec_layers(specs));
+        assert_eq!(
+            layers,
+            vec![vec!["db"], vec!["worker:0", "worker:1"], vec!["web"]]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+
+use persist_core::protocol::{LogEntry, LogLevel};
+
+use crate::server::structured;
+
+/// The criteria that log entries must meet to be sent to a client.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    /// Only keep the entries captured since this time.
+    pub since: Option<DateTime<Local>>,
+    /// Only keep the entries captured until this time.
+    pub until: Option<DateTime<Local>>,
+    /// Only keep the structured entries of at least this level.
+    pub level: Option<LogLevel>,
+    /// Only keep the structured entries having these values for these fields.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl EntryFilter {
+    /// Checks whether a log entry got captured within the time range.
+    ///
+    /// Entries without a timestamp are only considered within unbounded ranges.
+    pub fn is_within(&self, entry: &LogEntry) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+
+        let Some(timestamp) = entry.timestamp else {
+            return false;
+        };
+
+        self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp <= until)
+    }
+
+    /// Checks whether a log entry meets every criteria.
+    ///
+    /// Entries that are not structured never meet criteria on their level or fields.
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if !self.is_within(entry) {
+            return false;
+        }
+        if self.level.is_none() && self.fields.is_empty() {
+            return true;
+        }
+
+        let Some(log) = entry.structured.as_ref() else {
+            return false;
+        };
+
+        let level = self
+            .level
+            .is_none_or(|min| log.level.is_some_and(|level| level >= min));
+        let fields = self
+            .fields
+            .iter()
+            .all(|(key, value)| structured::field(log, key).as_ref() == Some(value));
+
+        level && fields
+    }
+}
@@ -1,44 +1,419 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::OpenOptions as StdOpenOptions;
 use std::future::Future;
-use std::path::Path;
-use std::process::Stdio;
-use std::sync::Arc;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
 
-use futures::sink::SinkExt;
+use futures::future::{self, BoxFuture, FutureExt, Shared};
 use futures::stream::{self, Stream, StreamExt};
+use nix::errno::Errno;
 use nix::sys::signal::Signal;
+use nix::sys::stat::Mode;
 use nix::unistd::Pid;
-use tokio::fs::OpenOptions;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::unix::pipe::{self, Receiver};
 use tokio::process::Command;
-use tokio::sync::{broadcast, Notify};
-use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_util::codec::FramedRead;
 
+use persist_core::ansi;
 use persist_core::error::Error;
-use persist_core::protocol::{ProcessSpec, ProcessStatus};
+use persist_core::protocol::{
+    self, AnsiMode, ExitInfo, HealthInfo, HealthStatus, JobInfo, LifecycleEvent, LogEntry,
+    LogEvent, LogFormat, LogGap, LogStreamSource, LogsResponse, OverlapPolicy, ProcessInfo,
+    ProcessSpec, ProcessStatus, PtySpec, RestartPolicy, WatchTrigger,
+};
 
-use crate::server::codec::LogDecoder;
+use crate::server::codec::{self, LogDecoder};
+use crate::server::multiline::Grouper;
+use crate::server::pty::{self, Master};
+use crate::server::schedule;
+use crate::server::sinks::LogSinks;
+use crate::server::structured;
+use crate::server::tail;
+use crate::server::writer::{self, LogWriter};
+
+/// A future resolving once the process exits, with its exit status (if it could be collected).
+///
+/// It can be cloned and awaited from multiple places, and resolves immediately if the process already exited.
+pub type ExitFuture = Shared<BoxFuture<'static, Option<ExitStatus>>>;
+
+/// The number of chunks of input that can be waiting to be written to the stdin of a process.
+const INPUT_QUEUE: usize = 64;
+
+/// How long the log forwarders of a run get to write out what remains of its output, once it exited.
+const FLUSH_DELAY: Duration = Duration::from_secs(1);
 
 pub struct Inner {
     pub pid: Pid,
-    pub ended: Arc<Notify>,
+    pub exit: ExitFuture,
+    pub started_at: Instant,
+}
+
+impl Inner {
+    /// Builds the exit information of this run, from its exit status.
+    fn exit_info(&self, status: Option<ExitStatus>, stopped: bool) -> ExitInfo {
+        ExitInfo {
+            stopped,
+            code: status.and_then(|status| status.code()),
+            signal: status.and_then(|status| status.signal()),
+            exited_at: chrono::Local::now().naive_local(),
+            duration: self.started_at.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Where the output of a newly spawned process gets captured from.
+enum Output {
+    /// The named pipes of its stdout and stderr.
+    Pipes { stdout: Receiver, stderr: Receiver },
+    /// The master side of its pseudo-terminal.
+    Terminal(Master),
+}
+
+/// A process that got signaled to stop, but that may not have exited yet.
+pub struct Stopping {
+    process: Inner,
+    timeout: Duration,
+}
+
+impl Stopping {
+    /// Waits for the process to exit, forcibly killing it if it does not do so in time.
+    pub async fn wait(self) -> Result<ExitInfo, Error> {
+        let exit = self.process.exit.clone();
+        let status = match tokio::time::timeout(self.timeout, exit.clone()).await {
+            Ok(status) => status,
+            Err(_) => {
+                signal_group(self.process.pid, Signal::SIGKILL)?;
+                exit.await
+            }
+        };
+
+        Ok(self.process.exit_info(status, true))
+    }
+}
+
+/// What a scheduled run of a job came to.
+pub enum Run {
+    /// The run got started.
+    Started(ExitFuture),
+    /// The run got skipped or queued, because the previous one is still going.
+    Deferred,
+    /// The previous run got signaled to stop, and the new one is to be started once it exited.
+    Replacing(Stopping),
+}
+
+/// Lets the log forwarders of a run that exited write out what remains of its output.
+///
+/// They get aborted if that takes too long (as when processes it left behind still hold its pipes),
+/// so that they never keep reading from the same named pipes as the next run.
+async fn finish_forwarding(forwarders: Vec<JoinHandle<()>>) {
+    let aborts: Vec<AbortHandle> = forwarders.iter().map(JoinHandle::abort_handle).collect();
+
+    let flushed = future::join_all(forwarders);
+    if tokio::time::timeout(FLUSH_DELAY, flushed).await.is_err() {
+        aborts.iter().for_each(AbortHandle::abort);
+    }
+}
+
+/// Creates a named pipe at the given path, unless one already exists there.
+fn create_pipe(path: &Path) -> Result<(), Error> {
+    match nix::unistd::mkfifo(path, Mode::S_IRUSR | Mode::S_IWUSR) {
+        Ok(()) | Err(Errno::EEXIST) => Ok(()),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Sends a signal to a whole process group, ignoring the case where that group is already gone.
+fn signal_group(pid: Pid, signal: Signal) -> Result<(), Error> {
+    match nix::sys::signal::killpg(pid, signal) {
+        Ok(()) | Err(Errno::ESRCH) => Ok(()),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Writes a captured log entry into its log file, sends it to the additional destinations,
+/// and broadcasts it to the live subscribers.
+///
+/// ANSI escape sequences are removed from what gets stored and/or streamed, according to the process' ANSI mode.
+async fn emit(
+    writer: &mut LogWriter,
+    sinks: &mut LogSinks,
+    sender: &broadcast::Sender<LogEntry>,
+    (name, source, format): (&str, LogStreamSource, LogFormat),
+    ansi: AnsiMode,
+    (timestamp, msg): (chrono::DateTime<chrono::Local>, String),
+) {
+    let stripped = ansi::strip(&msg);
+    let stored = match ansi.keep_stored() {
+        true => msg.as_str(),
+        false => stripped.as_ref(),
+    };
+
+    let line = codec::encode_line(&timestamp, stored);
+    if let Err(err) = writer.write_line(&line).await {
+        eprintln!("could not write to log file: {}", err);
+    }
+
+    let structured = structured::parse(format, &stripped);
+    let level = structured.as_ref().and_then(|log| log.level);
+    sinks.send(&timestamp, stored, level);
+
+    let msg = match ansi.keep_live() {
+        true => msg,
+        false => stripped.into_owned(),
+    };
+
+    let _ = sender.send(LogEntry {
+        source,
+        structured,
+        name: name.to_string(),
+        context: false,
+        timestamp: Some(timestamp),
+        msg,
+    });
+}
+
+/// Forwards the lines captured from one of the output streams of a process to its log file,
+/// additional destinations and live subscribers, grouping them into multi-line entries if requested.
+async fn forward<R: AsyncRead + Unpin>(
+    mut lines: FramedRead<R, LogDecoder>,
+    mut writer: LogWriter,
+    mut sinks: LogSinks,
+    sender: broadcast::Sender<LogEntry>,
+    (name, source, format): (String, LogStreamSource, LogFormat),
+    ansi: AnsiMode,
+    mut grouper: Option<Grouper>,
+) {
+    let origin = (name.as_str(), source, format);
+    loop {
+        let next = match grouper.as_ref().and_then(Grouper::deadline) {
+            Some(deadline) => tokio::time::timeout_at(deadline, lines.next()).await.ok(),
+            None => Some(lines.next().await),
+        };
+
+        let entry = match next {
+            //? no more lines arrived in time, so the pending entry is complete.
+            None => grouper.as_mut().and_then(Grouper::flush),
+            Some(None) => break,
+            Some(Some(Err(_))) => continue,
+            Some(Some(Ok(msg))) => {
+                let timestamp = chrono::Local::now();
+                match grouper.as_mut() {
+                    Some(grouper) => grouper.push(timestamp, msg),
+                    None => Some((timestamp, msg)),
+                }
+            }
+        };
+
+        if let Some(entry) = entry {
+            emit(&mut writer, &mut sinks, &sender, origin, ansi, entry).await;
+        }
+    }
+
+    if let Some(entry) = grouper.as_mut().and_then(Grouper::flush) {
+        emit(&mut writer, &mut sinks, &sender, origin, ansi, entry).await;
+    }
+}
+
+/// The channels broadcasting the output and the lifecycle events of a process, to the clients following its logs.
+///
+/// They are kept by the daemon across the successive handles of a process (with the same name),
+/// so that following its logs survives it being deleted and created again.
+#[derive(Clone)]
+pub struct LogChannels {
+    stdout: broadcast::Sender<LogEntry>,
+    stderr: broadcast::Sender<LogEntry>,
+    events: broadcast::Sender<LogEvent>,
+    capacity: usize,
+}
+
+impl LogChannels {
+    /// Creates new channels, each buffering up to `capacity` entries for lagging subscribers.
+    ///
+    /// The capacity is capped, as specifications loaded from the state file or restored from a dump are not validated.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.clamp(1, protocol::MAX_LOG_BUFFER);
+        let (stdout, _) = broadcast::channel(capacity);
+        let (stderr, _) = broadcast::channel(capacity);
+        let (events, _) = broadcast::channel(capacity);
+        Self {
+            stdout,
+            stderr,
+            events,
+            capacity,
+        }
+    }
+
+    /// Whether these channels buffer as many entries as requested.
+    pub fn fits(&self, capacity: usize) -> bool {
+        self.capacity == capacity.clamp(1, protocol::MAX_LOG_BUFFER)
+    }
+
+    /// Whether some clients are currently following these channels.
+    pub fn is_followed(&self) -> bool {
+        self.stdout.receiver_count() > 0
+            || self.stderr.receiver_count() > 0
+            || self.events.receiver_count() > 0
+    }
+
+    /// Reports a lifecycle event of the process.
+    pub fn send_event(&self, name: &str, event: LifecycleEvent) {
+        let _ = self.events.send(LogEvent {
+            event,
+            name: name.to_string(),
+            timestamp: chrono::Local::now(),
+        });
+    }
+
+    /// Subscribes to the lifecycle events of the process.
+    ///
+    /// Events missed because of lagging behind are skipped.
+    pub fn events(&self) -> impl Stream<Item = LogsResponse> {
+        stream::unfold(self.events.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((LogsResponse::Event(event), receiver)),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
+        })
+    }
+
+    /// Subscribes to the live output of one of the process' streams.
+    ///
+    /// If the subscriber lags behind, the skipped entries are recovered from the log file at `path` when `resume` is set
+    /// (and still possible), otherwise a gap is reported in their place.
+    pub fn subscribe(
+        &self,
+        (name, source, format): (String, LogStreamSource, LogFormat),
+        mode: AnsiMode,
+        path: PathBuf,
+        resume: bool,
+    ) -> impl Stream<Item = LogsResponse> {
+        let receiver = match source {
+            LogStreamSource::Stdout => self.stdout.subscribe(),
+            LogStreamSource::Stderr => self.stderr.subscribe(),
+        };
+
+        let state = (receiver, VecDeque::new());
+        stream::unfold(state, move |(mut receiver, mut pending)| {
+            let path = path.clone();
+            let name = name.clone();
+            async move {
+                if let Some(item) = pending.pop_front() {
+                    return Some((item, (receiver, pending)));
+                }
+
+                let mut skipped = 0;
+                let entry = loop {
+                    match receiver.recv().await {
+                        Ok(entry) => break entry,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                        Err(broadcast::error::RecvError::Lagged(count)) => skipped += count,
+                    }
+                };
+
+                if skipped > 0 {
+                    //? the skipped entries are the ones directly preceding the oldest entry still buffered.
+                    let recovered = match (resume, entry.timestamp.as_ref()) {
+                        (true, Some(timestamp)) => {
+                            //? the escape sequences may only have been kept in the live stream.
+                            let stored = match mode.keep_stored() {
+                                true => Cow::Borrowed(entry.msg.as_str()),
+                                false => ansi::strip(&entry.msg),
+                            };
+                            let anchor = codec::encode_line(timestamp, &stored);
+                            tail::lines_before(&path, &anchor, skipped as usize)
+                                .await
+                                .ok()
+                                .flatten()
+                        }
+                        _ => None,
+                    };
+
+                    match recovered {
+                        Some(lines) => {
+                            pending.extend(lines.iter().map(|line| {
+                                let (timestamp, msg) = codec::decode_line(line);
+                                LogsResponse::Entry(LogEntry {
+                                    source,
+                                    timestamp,
+                                    name: name.clone(),
+                                    context: false,
+                                    structured: structured::parse(format, &msg),
+                                    msg: msg.into_owned(),
+                                })
+                            }));
+                        }
+                        None => {
+                            pending.push_back(LogsResponse::Gap(LogGap {
+                                source,
+                                skipped,
+                                name: name.clone(),
+                            }));
+                        }
+                    }
+                    pending.push_back(LogsResponse::Entry(entry));
+
+                    let item = pending.pop_front()?;
+                    return Some((item, (receiver, pending)));
+                }
+
+                Some((LogsResponse::Entry(entry), (receiver, pending)))
+            }
+        })
+    }
 }
 
 pub struct ProcessHandle {
     pub(crate) spec: ProcessSpec,
     pub(crate) process: Option<Inner>,
-    pub(crate) stdout: broadcast::Sender<String>,
-    pub(crate) stderr: broadcast::Sender<String>,
+    pub(crate) channels: LogChannels,
+    /// The number of consecutive automatic restarts performed so far.
+    pub(crate) restarts: u32,
+    /// The task waiting to automatically restart the process, if one is scheduled.
+    pub(crate) pending_restart: Option<JoinHandle<()>>,
+    /// How the last run of the process ended.
+    pub(crate) last_exit: Option<ExitInfo>,
+    /// The health of the current run of the process (if it has a health check).
+    pub(crate) health: Option<HealthInfo>,
+    /// The channel to the task writing to the stdin of the current run of the process (if it is kept open).
+    pub(crate) stdin: Option<mpsc::Sender<Vec<u8>>>,
+    /// The master side of the pseudo-terminal of the current run of the process (if it runs in one).
+    pub(crate) terminal: Option<Master>,
+    /// The runs of the process so far (if it runs as a job).
+    pub(crate) job: JobInfo,
+    /// The task running the job every time its schedule comes up, if it is scheduled.
+    pub(crate) schedule: Option<JoinHandle<()>>,
+    /// The task watching the files of the process for changes (if it has watched files).
+    pub(crate) watcher: Option<JoinHandle<()>>,
+    /// The file changes that last triggered a restart of the process.
+    pub(crate) last_change: Option<WatchTrigger>,
 }
 
 impl ProcessHandle {
-    /// Creates a new process handle (but does not spawn the process).
-    pub fn new(spec: ProcessSpec) -> Self {
-        let (stdout, _) = broadcast::channel(15);
-        let (stderr, _) = broadcast::channel(15);
+    /// Creates a new process handle (but does not spawn the process), broadcasting its logs through the given channels.
+    pub fn new(spec: ProcessSpec, channels: LogChannels) -> Self {
         Self {
             spec,
-            stdout,
-            stderr,
+            channels,
             process: None,
+            restarts: 0,
+            pending_restart: None,
+            last_exit: None,
+            health: None,
+            stdin: None,
+            terminal: None,
+            job: JobInfo::default(),
+            schedule: None,
+            watcher: None,
+            last_change: None,
         }
     }
 
@@ -51,9 +426,40 @@ impl ProcessHandle {
     }
 
     pub fn status(&self) -> ProcessStatus {
-        self.process
+        if self.process.is_some() {
+            return ProcessStatus::Running;
+        }
+        if self
+            .schedule
             .as_ref()
-            .map_or(ProcessStatus::Stopped, |_| ProcessStatus::Running)
+            .is_some_and(|task| !task.is_finished())
+        {
+            return ProcessStatus::Scheduled;
+        }
+        //? cron jobs only stop being scheduled by getting stopped, whatever their last run did.
+        if schedule::is_scheduled(self.spec.job.as_ref()) {
+            return ProcessStatus::Stopped;
+        }
+
+        match self.last_exit.as_ref() {
+            None => ProcessStatus::Stopped,
+            Some(exit) if exit.stopped => ProcessStatus::Stopped,
+            Some(exit) if exit.success() => ProcessStatus::Exited,
+            Some(_) => ProcessStatus::Crashed,
+        }
+    }
+
+    pub fn last_exit(&self) -> Option<&ExitInfo> {
+        self.last_exit.as_ref()
+    }
+
+    pub fn health(&self) -> Option<&HealthInfo> {
+        self.process.as_ref().and(self.health.as_ref())
+    }
+
+    /// Gets information about the runs of the process, if it runs as a job.
+    pub fn runs(&self) -> Option<JobInfo> {
+        self.spec.job.as_ref().map(|_| self.job.clone())
     }
 
     pub fn pid(&self) -> Option<usize> {
@@ -62,6 +468,44 @@ impl ProcessHandle {
             .map(|handle| handle.pid.as_raw() as usize)
     }
 
+    pub fn info(&self) -> ProcessInfo {
+        let spec = self.spec();
+        ProcessInfo {
+            pid: self.pid(),
+            status: self.status(),
+            restarts: self.restarts,
+            exit: self.last_exit.clone(),
+            name: spec.name.clone(),
+            cmd: spec.cmd.clone(),
+            cwd: spec.cwd.clone(),
+            env: spec.env.clone(),
+            restart: spec.restart.clone(),
+            stop_signal: spec.stop_signal.clone(),
+            stop_timeout: spec.stop_timeout,
+            health_check: spec.health_check.clone(),
+            health: self.health().cloned(),
+            log_rotation: spec.log_rotation.clone(),
+            log_buffer: spec.log_buffer,
+            multiline: spec.multiline.clone(),
+            log_format: spec.log_format,
+            log_sinks: spec.log_sinks.clone(),
+            ansi: spec.ansi,
+            stdin: spec.stdin,
+            pty: spec.pty.clone(),
+            depends_on: spec.depends_on.clone(),
+            job: spec.job.clone(),
+            runs: self.runs(),
+            watch: spec.watch.clone(),
+            last_change: self.last_change.clone(),
+            instances: spec.instances.clone(),
+            instance: spec.instance,
+            created_at: spec.created_at,
+            pid_path: spec.pid_path.clone(),
+            stdout_path: spec.stdout_path.clone(),
+            stderr_path: spec.stderr_path.clone(),
+        }
+    }
+
     pub fn pid_file(&self) -> &Path {
         self.spec.pid_path.as_path()
     }
@@ -74,68 +518,237 @@ impl ProcessHandle {
         self.spec.stderr_path.as_path()
     }
 
-    pub fn stdout(&self) -> impl Stream<Item = String> {
-        stream::unfold(self.stdout.subscribe(), |mut stdout| async move {
-            loop {
-                match stdout.recv().await {
-                    Ok(item) => return Some((item, stdout)),
-                    Err(broadcast::error::RecvError::Closed) => return None,
-                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+    /// Gets the paths of every log file of the process, including the rotated ones it may retain.
+    pub fn log_files(&self) -> Vec<PathBuf> {
+        let rotation = &self.spec.log_rotation;
+        let mut files = vec![self.spec.stdout_path.clone(), self.spec.stderr_path.clone()];
+        files.extend(writer::rotated_paths(self.stdout_file(), rotation));
+        files.extend(writer::rotated_paths(self.stderr_file(), rotation));
+        files
+    }
+
+    /// Gets the paths of the log files of one of the process' streams, from the oldest rotated one to the current one.
+    ///
+    /// Some of these files may not exist.
+    pub fn log_history(&self, source: LogStreamSource) -> Vec<PathBuf> {
+        let path = match source {
+            LogStreamSource::Stdout => self.stdout_file(),
+            LogStreamSource::Stderr => self.stderr_file(),
+        };
+
+        let mut files: Vec<PathBuf> = (1..=self.spec.log_rotation.retain)
+            .rev()
+            .flat_map(|index| {
+                let compressed = writer::rotated_path(path, index, true);
+                let plain = writer::rotated_path(path, index, false);
+                [compressed, plain]
+            })
+            .collect();
+        files.push(path.to_path_buf());
+        files
+    }
+
+    pub fn pipe_files(&self) -> Vec<PathBuf> {
+        vec![
+            self.pipe_path("out"),
+            self.pipe_path("err"),
+            self.pipe_path("in"),
+        ]
+    }
+
+    /// Gets the path of the named pipe used to capture one of the output streams of the process
+    /// (or to feed its stdin).
+    ///
+    /// Named pipes (unlike anonymous ones) outlive the daemon, which allows a future daemon
+    /// to resume capturing the output of processes it re-adopts.
+    fn pipe_path(&self, suffix: &str) -> PathBuf {
+        let name = format!("{}-{}.pipe", self.spec.name, suffix);
+        self.spec.pid_path.with_file_name(name)
+    }
+
+    /// Opens the log files, and starts forwarding the output captured from the given streams to them.
+    ///
+    /// Processes running in a pseudo-terminal have no separate stderr to capture.
+    async fn forward_logs<R>(
+        &mut self,
+        pid: usize,
+        stdout: R,
+        stderr: Option<Receiver>,
+    ) -> Result<Vec<JoinHandle<()>>, Error>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let rotation = self.spec.log_rotation.clone();
+        let stdout_writer = LogWriter::open(&self.spec.stdout_path, rotation.clone()).await?;
+
+        let name = self.spec.name.as_str();
+        let sinks = &self.spec.log_sinks;
+        let stdout_sinks = LogSinks::open(sinks, name, pid, LogStreamSource::Stdout);
+
+        let stdout = FramedRead::new(stdout, LogDecoder::new());
+
+        //? the multiline rule is validated before managing the process, so it cannot fail here.
+        let grouper = || {
+            self.spec
+                .multiline
+                .as_ref()
+                .and_then(|spec| Grouper::new(spec).ok())
+        };
+
+        let stdout_forwarder = tokio::spawn(forward(
+            stdout,
+            stdout_writer,
+            stdout_sinks,
+            self.channels.stdout.clone(),
+            (
+                self.spec.name.clone(),
+                LogStreamSource::Stdout,
+                self.spec.log_format,
+            ),
+            self.spec.ansi,
+            grouper(),
+        ));
+        let mut forwarders = vec![stdout_forwarder];
+
+        if let Some(stderr) = stderr {
+            let stderr_writer = LogWriter::open(&self.spec.stderr_path, rotation).await?;
+            let stderr_sinks = LogSinks::open(sinks, name, pid, LogStreamSource::Stderr);
+            let stderr = FramedRead::new(stderr, LogDecoder::new());
+            let stderr_forwarder = tokio::spawn(forward(
+                stderr,
+                stderr_writer,
+                stderr_sinks,
+                self.channels.stderr.clone(),
+                (
+                    self.spec.name.clone(),
+                    LogStreamSource::Stderr,
+                    self.spec.log_format,
+                ),
+                self.spec.ansi,
+                grouper(),
+            ));
+            forwarders.push(stderr_forwarder);
+        }
+
+        Ok(forwarders)
+    }
+
+    /// Opens the daemon's end of the process' stdin pipe, and starts writing the input sent to the process into it.
+    ///
+    /// The child's end of the pipe must already be open, for it to be opened without blocking.
+    fn open_stdin(&mut self) -> Result<(), Error> {
+        let pipe = pipe::OpenOptions::new().open_sender(self.pipe_path("in"))?;
+        self.feed_stdin(pipe);
+        Ok(())
+    }
+
+    /// Starts writing the input sent to the process into the given stream.
+    fn feed_stdin<W>(&mut self, mut input: W)
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::channel::<Vec<u8>>(INPUT_QUEUE);
+
+        let name = self.spec.name.clone();
+        tokio::spawn(async move {
+            while let Some(data) = receiver.recv().await {
+                let written = match input.write_all(&data).await {
+                    Ok(()) => input.flush().await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = written {
+                    eprintln!("could not write to the stdin of '{}': {}", name, err);
+                    break;
                 }
             }
-        })
+        });
+
+        self.stdin = Some(sender);
     }
 
-    pub fn stderr(&self) -> impl Stream<Item = String> {
-        stream::unfold(self.stderr.subscribe(), |mut stderr| async move {
-            loop {
-                match stderr.recv().await {
-                    Ok(item) => return Some((item, stderr)),
-                    Err(broadcast::error::RecvError::Closed) => return None,
-                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
-                }
+    /// Changes the window size of the pseudo-terminal of the running process.
+    pub fn resize(&self, rows: u16, cols: u16) -> Result<(), Error> {
+        match (self.process.as_ref(), self.terminal.as_ref()) {
+            (Some(_), Some(terminal)) => pty::resize(terminal, rows, cols),
+            (None, _) => Err(Error::from(format!(
+                "process '{}' is not running",
+                self.spec.name
+            ))),
+            (Some(_), None) => Err(Error::from(format!(
+                "process '{}' does not run in a pseudo-terminal",
+                self.spec.name
+            ))),
+        }
+    }
+
+    /// Sends some input to the stdin of the running process.
+    ///
+    /// It gets written in the background, so that a process not reading its input does not block the daemon
+    /// (and gets rejected once too much of it is waiting to be written).
+    pub fn send_input(&self, data: Vec<u8>) -> Result<(), Error> {
+        if self.process.is_none() {
+            return Err(Error::from(format!(
+                "process '{}' is not running",
+                self.spec.name
+            )));
+        }
+
+        let stdin = self.stdin.as_ref().ok_or_else(|| {
+            Error::from(format!(
+                "process '{}' does not accept input (it must be started with `--stdin` or `--pty`)",
+                self.spec.name
+            ))
+        })?;
+
+        stdin.try_send(data).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => Error::from(format!(
+                "process '{}' is not reading its input fast enough",
+                self.spec.name
+            )),
+            mpsc::error::TrySendError::Closed(_) => {
+                Error::from(format!("the stdin of '{}' got closed", self.spec.name))
             }
         })
     }
 
-    pub async fn start(&mut self) -> Result<impl Future<Output = ()>, Error> {
-        let stdout_sink = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.spec.stdout_path)
-            .await?;
-        let mut stdout_sink = FramedWrite::new(stdout_sink, LinesCodec::new());
+    /// Spawns the process, reporting it to the clients following its logs.
+    pub async fn start(&mut self) -> Result<ExitFuture, Error> {
+        let exit = self.spawn().await?;
+        let pid = self.pid().unwrap_or_default();
 
-        let stderr_sink = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.spec.stderr_path)
-            .await?;
-        let mut stderr_sink = FramedWrite::new(stderr_sink, LinesCodec::new());
+        //? a handle only gets started again (without a restart request) by its restart policy.
+        let event = match self.restarts {
+            0 => LifecycleEvent::Started { pid },
+            _ => LifecycleEvent::Restarted { pid },
+        };
+        self.channels.send_event(self.name(), event);
+
+        Ok(exit)
+    }
 
-        let (cmd, args) = self.spec.cmd.split_first().expect("empty command");
+    async fn spawn(&mut self) -> Result<ExitFuture, Error> {
+        let (cmd, args) = self
+            .spec
+            .cmd
+            .split_first()
+            .ok_or_else(|| Error::from(String::from("the command cannot be empty")))?;
 
-        let mut child = {
+        let (mut child, output) = {
             let mut command = Command::new(cmd);
 
             command
                 .args(args)
                 .env_clear()
                 .envs(self.spec.env.iter())
-                .current_dir(self.spec.cwd.as_path())
-                .stdin(Stdio::null())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            unsafe {
-                command.pre_exec(|| {
-                    let pid = nix::unistd::getpid();
-                    nix::unistd::setpgid(pid, pid)?;
-                    Ok(())
-                });
-            }
+                .current_dir(self.spec.cwd.as_path());
 
-            command.spawn()?
+            let output = match self.spec.pty.as_ref() {
+                Some(spec) => self.open_terminal(&mut command, spec)?,
+                None => self.open_pipes(&mut command)?,
+            };
+
+            //? the command gets dropped right away, to close our copies of the child's ends.
+            (command.spawn()?, output)
         };
 
         let pid = match child.id() {
@@ -147,77 +760,366 @@ impl ProcessHandle {
             }
         };
 
-        let ended = Arc::new(Notify::new());
+        let started_at = Instant::now();
+
+        tokio::fs::write(self.spec.pid_path.clone(), pid.to_string()).await?;
+
+        self.stdin = None;
+        self.terminal = None;
+        let forwarders = match output {
+            Output::Pipes { stdout, stderr } => {
+                let forwarders = self
+                    .forward_logs(pid as usize, stdout, Some(stderr))
+                    .await?;
+                if self.spec.stdin {
+                    self.open_stdin()?;
+                }
+                forwarders
+            }
+            Output::Terminal(master) => {
+                let forwarders = self
+                    .forward_logs(pid as usize, master.clone(), None)
+                    .await?;
+                self.feed_stdin(master.clone());
+                self.terminal = Some(master);
+                forwarders
+            }
+        };
+
+        //? the run only counts as exited once its output got written out, so that the next one
+        //? (which reuses the same named pipes) never gets started while they are still being read.
+        let exit = async move {
+            let status = child.wait().await.ok();
+            finish_forwarding(forwarders).await;
+            status
+        }
+        .boxed()
+        .shared();
 
         let inner = Inner {
             pid: Pid::from_raw(pid as _),
-            ended: Arc::clone(&ended),
+            exit: exit.clone(),
+            started_at,
         };
 
-        tokio::fs::write(self.spec.pid_path.clone(), pid.to_string()).await?;
+        self.process.replace(inner);
+        self.job.last_run = Some(chrono::Local::now().naive_local());
+        self.reset_health();
 
-        let stdout = child.stdout.take().expect("failed to capture stdout");
-        let stderr = child.stderr.take().expect("failed to capture stderr");
-        let mut stdout = FramedRead::new(stdout, LogDecoder::new());
-        let mut stderr = FramedRead::new(stderr, LogDecoder::new());
+        //? make sure the child gets reaped, even if nobody is awaiting its termination.
+        tokio::spawn(exit.clone());
 
-        let sender = self.stdout.clone();
-        tokio::spawn(async move {
-            while let Some(item) = stdout.next().await {
-                if let Ok(item) = item {
-                    let _ = stdout_sink.send(item.clone()).await;
-                    let _ = sender.send(item);
-                }
+        Ok(exit)
+    }
+
+    /// Sets up the named pipes capturing the output of the process (and feeding its stdin, if kept open).
+    fn open_pipes(&self, command: &mut Command) -> Result<Output, Error> {
+        let stdout_path = self.pipe_path("out");
+        let stderr_path = self.pipe_path("err");
+        create_pipe(&stdout_path)?;
+        create_pipe(&stderr_path)?;
+
+        //? the child's ends are opened for both reading and writing, so that writing to them
+        //? never fails because of a missing reader (like when the daemon is not running).
+        //? they must be opened before our reading ends, which would otherwise see an EOF right away.
+        let stdout_writer = StdOpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&stdout_path)?;
+        let stderr_writer = StdOpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&stderr_path)?;
+        let stdout = pipe::OpenOptions::new().open_receiver(&stdout_path)?;
+        let stderr = pipe::OpenOptions::new().open_receiver(&stderr_path)?;
+
+        //? likewise, the child's end of the stdin pipe is opened for both reading and writing,
+        //? so that it never sees an EOF, even while no daemon holds the other end.
+        let stdin = match self.spec.stdin {
+            true => {
+                let stdin_path = self.pipe_path("in");
+                create_pipe(&stdin_path)?;
+                let stdin_reader = StdOpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&stdin_path)?;
+                Stdio::from(stdin_reader)
+            }
+            false => Stdio::null(),
+        };
+
+        command
+            .stdin(stdin)
+            .stdout(Stdio::from(stdout_writer))
+            .stderr(Stdio::from(stderr_writer));
+
+        unsafe {
+            command.pre_exec(|| {
+                let pid = nix::unistd::getpid();
+                nix::unistd::setpgid(pid, pid)?;
+                Ok(())
+            });
+        }
+
+        Ok(Output::Pipes { stdout, stderr })
+    }
+
+    /// Allocates the pseudo-terminal of the process, which becomes its stdin, stdout, stderr and controlling terminal.
+    ///
+    /// The process leads its own session, so it still gets its own process group.
+    fn open_terminal(&self, command: &mut Command, spec: &PtySpec) -> Result<Output, Error> {
+        let (master, slave) = pty::open(spec)?;
+
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        unsafe {
+            command.pre_exec(pty::set_controlling_terminal);
+        }
+
+        Ok(Output::Terminal(master))
+    }
+
+    /// Takes ownership of an already running process (left behind by a previous daemon), instead of spawning it.
+    ///
+    /// Its output is captured again from its named pipes, including what got buffered while no daemon was reading.  
+    /// Since it is not a child of this daemon, its exit status cannot be collected.
+    pub async fn adopt(&mut self, pid: Pid, started_at: Instant) -> Result<ExitFuture, Error> {
+        let stdout = pipe::OpenOptions::new().open_receiver(self.pipe_path("out"))?;
+        let stderr = pipe::OpenOptions::new().open_receiver(self.pipe_path("err"))?;
+
+        let forwarders = self
+            .forward_logs(pid.as_raw() as usize, stdout, Some(stderr))
+            .await?;
+        if self.spec.stdin {
+            self.open_stdin()?;
+        }
+
+        //? we cannot wait on a process that isn't our child, so we poll for its existence instead.
+        let exit = async move {
+            while nix::sys::signal::kill(pid, None).is_ok() {
+                tokio::time::sleep(Duration::from_millis(500)).await;
             }
+            finish_forwarding(forwarders).await;
+            None
+        }
+        .boxed()
+        .shared();
+
+        self.process.replace(Inner {
+            pid,
+            started_at,
+            exit: exit.clone(),
         });
+        self.reset_health();
 
-        let sender = self.stderr.clone();
-        tokio::spawn(async move {
-            while let Some(item) = stderr.next().await {
-                if let Ok(item) = item {
-                    let _ = stderr_sink.send(item.clone()).await;
-                    let _ = sender.send(item);
+        let pid = pid.as_raw() as usize;
+        self.channels
+            .send_event(self.name(), LifecycleEvent::Adopted { pid });
+
+        Ok(exit)
+    }
+
+    fn reset_health(&mut self) {
+        self.health = self
+            .spec
+            .health_check
+            .as_ref()
+            .map(|_| HealthInfo::default());
+    }
+
+    /// Records the result of a health check performed on the current run of the process.
+    ///
+    /// Returns whether the process should be restarted, because it just became unhealthy.
+    pub fn checked(&mut self, result: Result<(), String>) -> bool {
+        let (Some(check), Some(health)) = (self.spec.health_check.as_ref(), self.health.as_mut())
+        else {
+            return false;
+        };
+
+        health.checked_at = Some(chrono::Local::now().naive_local());
+        match result {
+            Ok(()) => {
+                health.status = HealthStatus::Healthy;
+                health.failures = 0;
+                health.error = None;
+                false
+            }
+            Err(err) => {
+                health.failures += 1;
+                health.error = Some(err);
+                if health.failures < check.retries.max(1)
+                    || health.status == HealthStatus::Unhealthy
+                {
+                    return false;
                 }
+                health.status = HealthStatus::Unhealthy;
+                check.restart
             }
-        });
+        }
+    }
 
-        self.process.replace(inner);
+    /// Records an unexpected exit of the process and decides, according to its restart policy,
+    /// whether it should be restarted.
+    ///
+    /// Returns the delay to wait before restarting it, or `None` if it should stay stopped.
+    pub fn exited(&mut self, status: Option<ExitStatus>) -> Option<Duration> {
+        let process = self.process.take()?;
+        let exit = process.exit_info(status, false);
+        self.channels
+            .send_event(self.name(), LifecycleEvent::Exited(exit.clone()));
+        self.last_exit = Some(exit);
+        let restart = &self.spec.restart;
 
-        tokio::spawn({
-            let ended = Arc::clone(&ended);
-            async move {
-                let _ = child.wait().await;
-                ended.notify_waiters();
+        let should_restart = match restart.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.is_some_and(|status| status.success()),
+            RestartPolicy::Always => true,
+        };
+        if !should_restart {
+            return None;
+        }
+
+        let max_backoff = Duration::from_millis(restart.max_backoff);
+        if process.started_at.elapsed() > max_backoff {
+            self.restarts = 0;
+        }
+
+        if matches!(restart.max_retries, Some(max_retries) if self.restarts >= max_retries) {
+            return None;
+        }
+
+        let factor = 1u64.checked_shl(self.restarts).unwrap_or(u64::MAX);
+        let backoff = restart
+            .backoff
+            .saturating_mul(factor)
+            .min(restart.max_backoff);
+        self.restarts += 1;
+
+        Some(Duration::from_millis(backoff))
+    }
+
+    /// Signals the process to stop, without waiting for it to exit.
+    ///
+    /// The signal and timeout default to the ones from the process' specification.
+    pub fn begin_stop(
+        &mut self,
+        signal: Option<Signal>,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Stopping>, Error> {
+        if let Some(task) = self.pending_restart.take() {
+            task.abort();
+        }
+
+        let signal = match signal {
+            Some(signal) => signal,
+            None => protocol::parse_signal(self.spec.stop_signal.as_str())?,
+        };
+        let timeout = timeout.unwrap_or_else(|| Duration::from_millis(self.spec.stop_timeout));
+
+        let Some(process) = self.process.take() else {
+            return Ok(None);
+        };
+
+        if let Err(err) = signal_group(process.pid, signal) {
+            self.process = Some(process);
+            return Err(err);
+        }
+
+        Ok(Some(Stopping { process, timeout }))
+    }
+
+    /// Starts a scheduled run of the job, applying its overlap policy if the previous run is still going.
+    pub async fn run(&mut self) -> Result<Run, Error> {
+        if self.process.is_some() {
+            let overlap = self.spec.job.as_ref().map(|job| job.overlap);
+            match overlap.unwrap_or_default() {
+                OverlapPolicy::Skip => {
+                    self.job.skipped += 1;
+                    self.channels
+                        .send_event(self.name(), LifecycleEvent::Skipped);
+                    return Ok(Run::Deferred);
+                }
+                OverlapPolicy::Queue => {
+                    self.job.queued = true;
+                    return Ok(Run::Deferred);
+                }
+                OverlapPolicy::Kill => {
+                    if let Some(stopping) = self.begin_stop(None, None)? {
+                        return Ok(Run::Replacing(stopping));
+                    }
+                }
             }
-        });
+        }
+
+        self.launch().await.map(Run::Started)
+    }
+
+    /// Starts a run of the job, once the previous one (if any) exited.
+    pub async fn launch(&mut self) -> Result<ExitFuture, Error> {
+        //? every run starts with a fresh restart policy.
+        self.restarts = 0;
+        self.job.queued = false;
+        self.start().await
+    }
+
+    /// Takes the run of the job waiting for the current one to exit, if there is one.
+    pub fn take_queued(&mut self) -> bool {
+        std::mem::take(&mut self.job.queued)
+    }
+
+    /// Stops running the job on its schedule (without stopping its current run).
+    pub fn unschedule(&mut self) {
+        if let Some(task) = self.schedule.take() {
+            task.abort();
+        }
+        self.job.next_run = None;
+        self.job.queued = false;
+    }
 
-        let future = async move {
-            ended.notified().await;
+    /// Records that some of the watched files changed, before restarting the process because of it.
+    pub fn files_changed(&mut self, paths: Vec<PathBuf>) {
+        let trigger = WatchTrigger {
+            paths,
+            changed_at: chrono::Local::now().naive_local(),
         };
+        self.channels
+            .send_event(self.name(), LifecycleEvent::Changed(trigger.clone()));
+        self.last_change = Some(trigger);
+    }
+
+    /// Stops watching the files of the process for changes.
+    pub fn unwatch(&mut self) {
+        if let Some(task) = self.watcher.take() {
+            task.abort();
+        }
+    }
 
-        Ok(future)
+    /// Records how the process ended after getting stopped on request.
+    pub fn stopped(&mut self, exit: ExitInfo) {
+        self.last_exit = Some(exit);
+        self.channels
+            .send_event(self.name(), LifecycleEvent::Stopped);
     }
 
     pub async fn stop(&mut self) -> Result<(), Error> {
-        if let Some(child) = self.process.take() {
-            let future = child.ended.notified();
-            nix::sys::signal::killpg(child.pid, Signal::SIGTERM)?;
-            let _ = future.await;
+        if let Some(stopping) = self.begin_stop(None, None)? {
+            let exit = stopping.wait().await?;
+            self.stopped(exit);
         }
         Ok(())
     }
 
-    pub async fn restart(&mut self) -> Result<impl Future<Output = ()>, Error> {
-        self.stop().await?;
-        self.start().await
-    }
+    /// Spawns the process again once its previous run got stopped, reporting it as restarted.
+    pub async fn respawn(&mut self) -> Result<ExitFuture, Error> {
+        let exit = self.spawn().await?;
+        let pid = self.pid().unwrap_or_default();
+        self.channels
+            .send_event(self.name(), LifecycleEvent::Restarted { pid });
 
-    pub async fn restart_with_spec(
-        &mut self,
-        spec: ProcessSpec,
-    ) -> Result<impl Future<Output = ()>, Error> {
-        self.spec = spec;
-        self.restart().await
+        Ok(exit)
     }
 
     pub async fn with_process<'a, F, Fut, T>(&'a self, func: F) -> Option<T>
@@ -225,10 +1127,9 @@ impl ProcessHandle {
         F: FnOnce(&'a Inner) -> Fut,
         Fut: Future<Output = T> + 'a,
     {
-        if let Some(process) = self.process.as_ref() {
-            Some(func(process).await)
-        } else {
-            None
+        match self.process.as_ref() {
+            Some(process) => Some(func(process).await),
+            None => None,
         }
     }
 
@@ -236,10 +1137,6 @@ impl ProcessHandle {
     where
         F: FnOnce(&'a Inner) -> T,
     {
-        if let Some(process) = self.process.as_ref() {
-            Some(func(process))
-        } else {
-            None
-        }
+        self.process.as_ref().map(func)
     }
 }
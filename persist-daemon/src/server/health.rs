@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+use persist_core::protocol::{HealthCheckSpec, HealthProbe};
+
+/// Performs a single health check, returning the reason of the failure if it did not succeed in time.
+pub async fn check(
+    spec: &HealthCheckSpec,
+    cwd: &Path,
+    env: &HashMap<String, String>,
+) -> Result<(), String> {
+    let timeout = Duration::from_millis(spec.timeout);
+    match tokio::time::timeout(timeout, probe(&spec.probe, cwd, env)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}ms", spec.timeout)),
+    }
+}
+
+async fn probe(
+    probe: &HealthProbe,
+    cwd: &Path,
+    env: &HashMap<String, String>,
+) -> Result<(), String> {
+    match probe {
+        HealthProbe::Exec { cmd } => {
+            let (cmd, args) = cmd.split_first().ok_or("empty command")?;
+            let status = Command::new(cmd)
+                .args(args)
+                .env_clear()
+                .envs(env.iter())
+                .current_dir(cwd)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .status()
+                .await
+                .map_err(|err| format!("could not run command: {}", err))?;
+
+            if status.success() {
+                Ok(())
+            } else {
+                Err(format!("command failed ({})", status))
+            }
+        }
+        HealthProbe::Tcp { port } => {
+            TcpStream::connect(("127.0.0.1", *port))
+                .await
+                .map_err(|err| format!("could not connect: {}", err))?;
+            Ok(())
+        }
+        HealthProbe::Http { port, path } => {
+            let mut stream = TcpStream::connect(("127.0.0.1", *port))
+                .await
+                .map_err(|err| format!("could not connect: {}", err))?;
+
+            let request = format!(
+                "GET {} HTTP/1.0\r\nHost: localhost:{}\r\nConnection: close\r\n\r\n",
+                path, port
+            );
+            stream
+                .write_all(request.as_bytes())
+                .await
+                .map_err(|err| format!("could not send request: {}", err))?;
+
+            //? we only need the status line, which is expected to fit in the first few bytes.
+            let mut buffer = Vec::with_capacity(256);
+            let mut chunk = [0u8; 256];
+            while !buffer.contains(&b'\n') && buffer.len() < 4096 {
+                let read = stream
+                    .read(&mut chunk)
+                    .await
+                    .map_err(|err| format!("could not read response: {}", err))?;
+                if read == 0 {
+                    break;
+                }
+                buffer.extend_from_slice(&chunk[..read]);
+            }
+
+            let response = String::from_utf8_lossy(&buffer);
+            let code = response
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or("invalid HTTP response")?;
+
+            if (200..400).contains(&code) {
+                Ok(())
+            } else {
+                Err(format!("responded with status {}", code))
+            }
+        }
+    }
+}
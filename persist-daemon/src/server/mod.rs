@@ -1,14 +1,29 @@
 use std::sync::Arc;
 
+use futures::future;
 use futures::sink::SinkExt;
 use futures::stream::StreamExt;
 use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
 use tokio_util::codec::{Framed, LinesCodec};
 
 pub mod codec;
+pub mod deps;
+pub mod filter;
 pub mod handle;
+pub mod health;
+pub mod multiline;
+pub mod pty;
+pub mod replicas;
 pub mod request;
+pub mod schedule;
+pub mod search;
+pub mod sinks;
 pub mod state;
+pub mod structured;
+pub mod tail;
+pub mod watcher;
+pub mod writer;
 
 use persist_core::daemon::{PID_FILE, SOCK_FILE};
 use persist_core::error::Error;
@@ -35,7 +50,7 @@ pub async fn handle_conn(state: Arc<State>, conn: UnixStream) -> Result<(), Erro
 
         let outcome = match request {
             Request::List(request) => list::handle(state.clone(), &mut framed, request).await,
-            Request::Start(request) => start::handle(state.clone(), &mut framed, request).await,
+            Request::Start(request) => start::handle(state.clone(), &mut framed, *request).await,
             Request::Stop(request) => stop::handle(state.clone(), &mut framed, request).await,
             Request::Restart(request) => restart::handle(state.clone(), &mut framed, request).await,
             Request::Info(request) => info::handle(state.clone(), &mut framed, request).await,
@@ -44,8 +59,13 @@ pub async fn handle_conn(state: Arc<State>, conn: UnixStream) -> Result<(), Erro
             Request::Dump(request) => dump::handle(state.clone(), &mut framed, request).await,
             Request::Restore(request) => restore::handle(state.clone(), &mut framed, request).await,
             Request::Prune(request) => prune::handle(state.clone(), &mut framed, request).await,
+            Request::Input(request) => input::handle(state.clone(), &mut framed, request).await,
+            Request::Resize(request) => resize::handle(state.clone(), &mut framed, request).await,
+            Request::Scale(request) => scale::handle(state.clone(), &mut framed, request).await,
             Request::Version => daemon::version::handle(&mut framed).await,
-            Request::Kill => std::process::exit(0),
+            Request::Kill(request) => {
+                daemon::kill::handle(state.clone(), &mut framed, request).await
+            }
         };
 
         if let Err(err) = outcome {
@@ -66,15 +86,45 @@ pub async fn start() -> Result<(), Error> {
     let pid = std::process::id();
     tokio::fs::write(PID_FILE, pid.to_string()).await?;
 
+    //? bring back the processes managed by the previous daemon, before persisting anything new.
+    if let Err(err) = state.clone().resurrect().await {
+        eprintln!("could not resurrect processes: {}", err);
+    }
+    tokio::spawn(state.clone().persist_changes());
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+
     loop {
-        if let Ok((conn, _)) = listener.accept().await {
-            let state = state.clone();
-            tokio::spawn(async move {
-                if let Err(err) = handle_conn(state, conn).await {
-                    // TODO: do something about this error ?
-                    eprintln!("conn error: {}", err);
+        tokio::select! {
+            accepted = listener.accept() => {
+                if let Ok((conn, _)) = accepted {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = handle_conn(state, conn).await {
+                            // TODO: do something about this error ?
+                            eprintln!("conn error: {}", err);
+                        }
+                    });
                 }
-            });
+            }
+            _ = sigterm.recv() => {
+                state.shutdown(false).await;
+                break;
+            }
+            _ = sigint.recv() => {
+                state.shutdown(false).await;
+                break;
+            }
+            _ = state.terminated() => break,
         }
     }
+
+    let _ = future::join(
+        tokio::fs::remove_file(SOCK_FILE),
+        tokio::fs::remove_file(PID_FILE),
+    )
+    .await;
+
+    Ok(())
 }
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+use tokio::time::Instant;
+
+use persist_core::ansi;
+use persist_core::error::Error;
+use persist_core::protocol::{MultilineRule, MultilineSpec};
+
+enum Rule {
+    Indent,
+    Start(Regex),
+}
+
+/// A multi-line entry, still waiting for more lines.
+struct Pending {
+    /// When the first line of the entry got captured.
+    timestamp: DateTime<Local>,
+    lines: Vec<String>,
+    /// When the entry gets considered complete if no more lines arrive for it.
+    deadline: Instant,
+}
+
+/// Groups consecutive lines of an output stream into multi-line log entries, according to a process' multiline rule.
+pub struct Grouper {
+    rule: Rule,
+    timeout: Duration,
+    max_lines: usize,
+    pending: Option<Pending>,
+}
+
+impl Grouper {
+    pub fn new(spec: &MultilineSpec) -> Result<Self, Error> {
+        let rule = match &spec.rule {
+            MultilineRule::Indent => Rule::Indent,
+            MultilineRule::Start { pattern } => {
+                let regex = Regex::new(pattern.as_str())
+                    .map_err(|err| format!("invalid multiline pattern: {}", err))?;
+                Rule::Start(regex)
+            }
+        };
+
+        Ok(Self {
+            rule,
+            timeout: Duration::from_millis(spec.timeout),
+            max_lines: spec.max_lines.max(1),
+            pending: None,
+        })
+    }
+
+    /// Whether a line continues the previous entry, rather than starting a new one.
+    ///
+    /// ANSI escape sequences are ignored, so that colored output gets grouped like plain output.
+    fn continues(&self, line: &str) -> bool {
+        let line = ansi::strip(line);
+        match &self.rule {
+            Rule::Indent => line.starts_with(char::is_whitespace),
+            Rule::Start(regex) => !regex.is_match(&line),
+        }
+    }
+
+    /// Gets the time at which the pending entry gets complete, if there is one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|pending| pending.deadline)
+    }
+
+    /// Feeds a newly captured line, returning the entry that it completes (if any).
+    pub fn push(
+        &mut self,
+        timestamp: DateTime<Local>,
+        line: String,
+    ) -> Option<(DateTime<Local>, String)> {
+        let continues = self.continues(line.as_str());
+        let deadline = Instant::now() + self.timeout;
+        if let Some(pending) = self.pending.as_mut() {
+            if continues && pending.lines.len() < self.max_lines {
+                pending.lines.push(line);
+                pending.deadline = deadline;
+                return None;
+            }
+        }
+
+        let completed = self.flush();
+        self.pending = Some(Pending {
+            timestamp,
+            deadline,
+            lines: vec![line],
+        });
+        completed
+    }
+
+    /// Takes the pending entry (if any), considering it complete.
+    pub fn flush(&mut self) -> Option<(DateTime<Local>, String)> {
+        let pending = self.pending.take()?;
+        Some((pending.timestamp, pending.lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+
+    fn grouper(rule: MultilineRule, max_lines: usize) -> Grouper {
+        Grouper::new(&MultilineSpec {
+            rule,
+            max_lines,
+            timeout: 100,
+        })
+        .unwrap()
+    }
+
+    /// Feeds lines to a grouper (captured a second apart), returning all the entries by the second of their first line.
+    fn group(grouper: &mut Grouper, lines: &[&str]) -> Vec<(u32, String)> {
+        let mut entries = Vec::new();
+        for (second, line) in lines.iter().enumerate() {
+            let timestamp = Local
+                .with_ymd_and_hms(2024, 1, 1, 0, 0, second as u32)
+                .unwrap();
+            entries.extend(grouper.push(timestamp, line.to_string()));
+        }
+        entries.extend(grouper.flush());
+
+        entries
+            .into_iter()
+            .map(|(timestamp, msg)| (timestamp.second(), msg))
+            .collect()
+    }
+
+    #[test]
+    fn indented_lines_continue_entries() {
+        let mut grouper = grouper(MultilineRule::Indent, 100);
+        let lines = [
+            "Exception in thread \"main\"",
+            "    at Main.run(Main.java:10)",
+            "\tat Main.main(Main.java:5)",
+            "done",
+        ];
+        let expected = vec![(0, lines[..3].join("\n")), (3, String::from("done"))];
+        assert_eq!(group(&mut grouper, &lines), expected);
+    }
+
+    #[test]
+    fn lines_not_matching_the_start_pattern_continue_entries() {
+        let start = MultilineRule::Start {
+            pattern: String::from(r"^\d{4}-\d{2}-\d{2}"),
+        };
+        let mut grouper = grouper(start, 100);
+        let lines = [
+            "2024-01-01 error",
+            "Traceback:",
+            "  line 1",
+            "2024-01-01 ok",
+        ];
+        let expected = vec![
+            (0, lines[..3].join("\n")),
+            (3, String::from("2024-01-01 ok")),
+        ];
+        assert_eq!(group(&mut grouper, &lines), expected);
+    }
+
+    #[test]
+    fn entries_are_split_past_the_maximum_number_of_lines() {
+        let mut grouper = grouper(MultilineRule::Indent, 2);
+        let lines = ["first", " a", " b", " c"];
+        let expected = vec![(0, String::from("first\n a")), (2, String::from(" b\n c"))];
+        assert_eq!(group(&mut grouper, &lines), expected);
+    }
+
+    #[test]
+    fn colors_are_ignored() {
+        let mut grouper = grouper(MultilineRule::Indent, 100);
+        let lines = ["\x1b[31merror\x1b[0m", "\x1b[31m  at here\x1b[0m"];
+        let expected = vec![(0, lines.join("\n"))];
+        assert_eq!(group(&mut grouper, &lines), expected);
+    }
+
+    #[test]
+    fn deadline_of_the_pending_entry() {
+        let mut grouper = grouper(MultilineRule::Indent, 100);
+        assert!(grouper.deadline().is_none());
+
+        grouper.push(Local::now(), String::from("first"));
+        let deadline = grouper.deadline().unwrap();
+        assert!(deadline > Instant::now());
+
+        grouper.flush();
+        assert!(grouper.deadline().is_none());
+    }
+
+    #[test]
+    fn invalid_start_pattern() {
+        let spec = MultilineSpec {
+            rule: MultilineRule::Start {
+                pattern: String::from("(unclosed"),
+            },
+            timeout: 100,
+            max_lines: 100,
+        };
+        assert!(Grouper::new(&spec).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use nix::errno::Errno;
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use nix::pty::{self, Winsize};
+use nix::sys::termios::Termios;
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use persist_core::error::Error;
+use persist_core::protocol::PtySpec;
+
+fn winsize(rows: u16, cols: u16) -> Winsize {
+    Winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// The master side of a pseudo-terminal, read from and written to without blocking.
+///
+/// It is driven by the runtime (rather than through blocking threads, like `tokio::fs::File` does),
+/// so that a terminal kept open by some other process (like a child of the process) holds up no thread.
+#[derive(Clone)]
+pub struct Master(Arc<AsyncFd<OwnedFd>>);
+
+impl Master {
+    fn new(fd: OwnedFd) -> Result<Master, Error> {
+        let flags = OFlag::from_bits_truncate(fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)?);
+        fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
+
+        //? the file descriptor is owned, so it stays open (and the same) for as long as it is registered.
+        let fd = unsafe { AsyncFd::register(fd) }.map_err(|err| Error::from(err.into_parts().1))?;
+        Ok(Master(Arc::new(fd)))
+    }
+}
+
+impl AsyncRead for Master {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.0.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            let outcome = guard.try_io(|fd| {
+                nix::unistd::read(fd.get_ref().as_raw_fd(), unfilled).map_err(io::Error::from)
+            });
+            match outcome {
+                Ok(Ok(count)) => {
+                    buf.advance(count);
+                    return Poll::Ready(Ok(()));
+                }
+                //? reading fails once every process using the terminal closed it, which is its end.
+                Ok(Err(err)) if err.raw_os_error() == Some(libc::EIO) => {
+                    return Poll::Ready(Ok(()))
+                }
+                Ok(Err(err)) => return Poll::Ready(Err(err)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Master {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.0.poll_write_ready(cx))?;
+            let outcome = guard.try_io(|fd| {
+                nix::unistd::write(fd.get_ref().as_raw_fd(), buf).map_err(io::Error::from)
+            });
+            match outcome {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Allocates a new pseudo-terminal, returning its master and slave sides.
+pub fn open(spec: &PtySpec) -> Result<(Master, File), Error> {
+    let size = winsize(spec.rows, spec.cols);
+    let pty = pty::openpty(Some(&size), None::<&Termios>)?;
+    Ok((Master::new(pty.master)?, File::from(pty.slave)))
+}
+
+/// Changes the window size of a pseudo-terminal, from its master side.
+///
+/// The foreground process group of the terminal gets notified of it by a `SIGWINCH`.
+pub fn resize(master: &Master, rows: u16, cols: u16) -> Result<(), Error> {
+    let size = winsize(rows, cols);
+    let outcome = unsafe { libc::ioctl(master.0.as_raw_fd(), libc::TIOCSWINSZ, &size) };
+    Errno::result(outcome)?;
+    Ok(())
+}
+
+/// Makes the calling process the leader of a new session, controlled by the terminal it has as its stdin.
+///
+/// It is meant to be called from the child process, right before it executes its command.
+pub fn set_controlling_terminal() -> io::Result<()> {
+    nix::unistd::setsid()?;
+    let outcome = unsafe { libc::ioctl(0, libc::TIOCSCTTY, 0) };
+    Errno::result(outcome)?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use persist_core::protocol::ProcessSpec;
+
+/// The environment variable holding the index of each replica.
+pub const INSTANCE_ENV: &str = "PERSIST_INSTANCE";
+
+/// Gets the name of one of the replicas of a process.
+pub fn name(group: &str, index: usize) -> String {
+    format!("{}:{}", group, index)
+}
+
+/// Gets the index of a replica from its name, if it is the name of one of the replicas of the given group.
+pub fn index_of(group: &str, name: &str) -> Option<usize> {
+    let index = name.strip_prefix(group)?.strip_prefix(':')?;
+    index.parse().ok()
+}
+
+/// Whether a process runs as several replicas, and is yet to be split into them.
+pub fn is_template(spec: &ProcessSpec) -> bool {
+    spec.instances.is_some() && spec.instance.is_none()
+}
+
+/// Sets the environment variables telling a replica which one it is (its index, and port if it gets one).
+pub fn inject_env(spec: &mut ProcessSpec) {
+    let (Some(index), Some(instances)) = (spec.instance, spec.instances.as_ref()) else {
+        return;
+    };
+
+    spec.env.insert(INSTANCE_ENV.to_string(), index.to_string());
+    if let Some(port) = instances.port_of(index) {
+        spec.env
+            .insert(instances.port_env.clone(), port.to_string());
+    }
+}
+
+/// Builds the specification of one of the replicas of a process (from the one of the process, or of any replica).
+pub fn replica(spec: &ProcessSpec, index: usize) -> ProcessSpec {
+    let mut replica = spec.clone();
+    replica.name = name(spec.group(), index);
+    replica.instance = Some(index);
+    inject_env(&mut replica);
+    replica
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use futures::sink::SinkExt;
+use tokio::net::UnixStream;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use persist_core::error::Error;
+use persist_core::protocol::{KillRequest, Response};
+
+use crate::server::State;
+
+pub async fn handle(
+    state: Arc<State>,
+    conn: &mut Framed<UnixStream, LinesCodec>,
+    req: KillRequest,
+) -> Result<(), Error> {
+    let response = state.shutdown(req.keep_processes).await;
+
+    let response = Response::Killed(response);
+    let serialized = json::to_string(&response)?;
+    let outcome = conn.send(serialized).await;
+
+    //? terminate even if the client went away in the meantime.
+    state.terminate();
+    outcome?;
+
+    Ok(())
+}
@@ -1 +1,2 @@
+pub mod kill;
 pub mod version;
@@ -16,7 +16,7 @@ pub async fn handle(
     req: DeleteRequest,
 ) -> Result<(), Error> {
     let names = match req.filters {
-        Some(filters) => filters,
+        Some(filters) => state.resolve(filters).await,
         None => {
             state
                 .with_handles(|handles| handles.keys().cloned().collect())
@@ -14,7 +14,11 @@ pub async fn handle(
     conn: &mut Framed<UnixStream, LinesCodec>,
     req: DumpRequest,
 ) -> Result<(), Error> {
-    let specs = state.dump(req.filters).await?;
+    let filters = match req.filters {
+        Some(filters) => Some(state.resolve(filters).await),
+        None => None,
+    };
+    let specs = state.dump(filters).await?;
 
     let responses = specs
         .into_iter()
@@ -5,7 +5,7 @@ use tokio::net::UnixStream;
 use tokio_util::codec::{Framed, LinesCodec};
 
 use persist_core::error::Error;
-use persist_core::protocol::{InfoRequest, InfoResponse, ProcessInfo, Response};
+use persist_core::protocol::{InfoRequest, InfoResponse, Response};
 
 use crate::server::State;
 
@@ -14,23 +14,7 @@ pub async fn handle(
     conn: &mut Framed<UnixStream, LinesCodec>,
     req: InfoRequest,
 ) -> Result<(), Error> {
-    let info = state
-        .with_handle(req.name, |handle| {
-            let spec = handle.spec();
-            ProcessInfo {
-                pid: handle.pid(),
-                status: handle.status(),
-                name: spec.name.clone(),
-                cmd: spec.cmd.clone(),
-                cwd: spec.cwd.clone(),
-                env: spec.env.clone(),
-                created_at: spec.created_at,
-                pid_path: spec.pid_path.clone(),
-                stdout_path: spec.stdout_path.clone(),
-                stderr_path: spec.stderr_path.clone(),
-            }
-        })
-        .await?;
+    let info = state.with_handle(req.name, |handle| handle.info()).await?;
 
     let response = Response::Info(InfoResponse { info });
     let serialized = json::to_string(&response)?;
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use futures::sink::SinkExt;
+use tokio::net::UnixStream;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use persist_core::error::Error;
+use persist_core::protocol::{InputRequest, Response};
+
+use crate::server::State;
+
+pub async fn handle(
+    state: Arc<State>,
+    conn: &mut Framed<UnixStream, LinesCodec>,
+    req: InputRequest,
+) -> Result<(), Error> {
+    let InputRequest { name, data } = req;
+    state
+        .with_handle(name, |handle| handle.send_input(data))
+        .await??;
+
+    let response = Response::Input;
+    let serialized = json::to_string(&response)?;
+    conn.send(serialized).await?;
+
+    Ok(())
+}
@@ -12,9 +12,13 @@ use crate::server::State;
 pub async fn handle(
     state: Arc<State>,
     conn: &mut Framed<UnixStream, LinesCodec>,
-    _request: ListRequest,
+    request: ListRequest,
 ) -> Result<(), Error> {
-    let metrics = state.list().await?;
+    let mut metrics = state.list().await?;
+    if let Some(filters) = request.filters {
+        let names = state.resolve(filters).await;
+        metrics.retain(|metric| names.contains(&metric.name));
+    }
 
     let response = Response::List(metrics);
     let serialized = json::to_string(&response)?;
@@ -13,18 +13,19 @@ use crate::server::State;
 pub async fn handle(
     state: Arc<State>,
     conn: &mut Framed<UnixStream, LinesCodec>,
-    req: LogsRequest,
+    mut req: LogsRequest,
 ) -> Result<(), Error> {
-    let mut logs = state
-        .logs(req.filters, req.lines, req.stream, req.source_filter)
-        .await?;
+    if let Some(filters) = req.filters.take() {
+        req.filters = Some(state.resolve(filters).await);
+    }
+    let mut logs = state.logs(req).await?;
 
     let response = Response::Logs(LogsResponse::Subscribed);
     let serialized = json::to_string(&response)?;
     conn.send(serialized).await?;
 
     while let Some(item) = logs.next().await {
-        let response = Response::Logs(LogsResponse::Entry(item));
+        let response = Response::Logs(item);
         let serialized = json::to_string(&response)?;
         conn.send(serialized).await?;
     }
@@ -2,10 +2,13 @@ pub mod daemon;
 pub mod delete;
 pub mod dump;
 pub mod info;
+pub mod input;
 pub mod list;
 pub mod logs;
 pub mod prune;
+pub mod resize;
 pub mod restart;
 pub mod restore;
+pub mod scale;
 pub mod start;
 pub mod stop;
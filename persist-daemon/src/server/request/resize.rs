@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use futures::sink::SinkExt;
+use tokio::net::UnixStream;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use persist_core::error::Error;
+use persist_core::protocol::{ResizeRequest, Response};
+
+use crate::server::State;
+
+pub async fn handle(
+    state: Arc<State>,
+    conn: &mut Framed<UnixStream, LinesCodec>,
+    req: ResizeRequest,
+) -> Result<(), Error> {
+    let ResizeRequest { name, rows, cols } = req;
+    state
+        .with_handle(name, |handle| handle.resize(rows, cols))
+        .await??;
+
+    let response = Response::Resize;
+    let serialized = json::to_string(&response)?;
+    conn.send(serialized).await?;
+
+    Ok(())
+}
@@ -8,6 +8,8 @@ use tokio_util::codec::{Framed, LinesCodec};
 use persist_core::error::Error;
 use persist_core::protocol::{Response, RestartRequest, RestartResponse};
 
+use crate::server::deps;
+use crate::server::replicas;
 use crate::server::State;
 
 pub async fn handle(
@@ -16,32 +18,34 @@ pub async fn handle(
     request: RestartRequest,
 ) -> Result<(), Error> {
     let names = match request.filters {
-        Some(names) => names,
+        Some(names) => state.resolve(names).await,
         None => {
             state
                 .with_handles(|handles| handles.keys().cloned().collect())
                 .await
         }
     };
-    let updated_env = request.env;
-
-    let futures = names.iter().cloned().map(|name| async {
-        let mut spec = state.spec(name).await?;
-        if let Some(ref env) = updated_env {
-            spec.env = env.clone();
-        }
-        state.clone().restart(spec).await
-    });
-
-    let responses = future::join_all(futures).await;
-    let responses = responses
-        .into_iter()
-        .zip(names.into_iter())
-        .map(|(res, name)| {
-            let error = res.err().map(|err| err.to_string());
+    let updated_env = &request.env;
+    let state = &state;
+
+    //? processes are restarted after the ones they depend on (and once these meet their conditions).
+    let mut responses = Vec::with_capacity(names.len());
+    for layer in deps::managed_layers(state, names).await {
+        let futures = layer.into_iter().map(|name| async move {
+            let res = async {
+                let mut spec = state.spec(&name).await?;
+                if let Some(env) = updated_env {
+                    spec.env = env.clone();
+                    replicas::inject_env(&mut spec);
+                }
+                deps::wait(state, &spec).await?;
+                state.clone().restart(spec).await
+            };
+            let error = res.await.err().map(|err| err.to_string());
             RestartResponse { name, error }
-        })
-        .collect::<Vec<_>>();
+        });
+        responses.extend(future::join_all(futures).await);
+    }
 
     let response = Response::Restart(responses);
     let serialized = json::to_string(&response)?;
@@ -6,8 +6,9 @@ use tokio::net::UnixStream;
 use tokio_util::codec::{Framed, LinesCodec};
 
 use persist_core::error::Error;
-use persist_core::protocol::{Response, RestoreRequest, RestoreResponse};
+use persist_core::protocol::{ProcessStatus, Response, RestoreRequest, RestoreResponse};
 
+use crate::server::deps;
 use crate::server::State;
 
 pub async fn handle(
@@ -15,16 +16,44 @@ pub async fn handle(
     conn: &mut Framed<UnixStream, LinesCodec>,
     req: RestoreRequest,
 ) -> Result<(), Error> {
-    let futures = req.specs.into_iter().map(|spec| async {
-        let name = spec.name.clone();
+    //? the restored processes must not introduce a dependency cycle, including with the already managed ones.
+    let graph = state
+        .with_handles(|handles| {
+            let managed = handles.values().map(|handle| handle.spec());
+            deps::graph(managed.chain(req.specs.iter()))
+        })
+        .await;
+    deps::layers(&graph)?;
+
+    //? processes are started after the ones they depend on (concurrently, when they don't depend on each other).
+    let layers = deps::layers(&deps::graph(req.specs.iter()))?;
+    let mut specs = req.specs;
+    let mut responses = Vec::with_capacity(specs.len());
+    for layer in layers {
+        let (current, rest) = specs
+            .into_iter()
+            .partition::<Vec<_>, _>(|spec| layer.contains(&spec.name));
+        specs = rest;
+
+        let futures = current.into_iter().map(|spec| async {
+            let name = spec.name.clone();
+
+            let res = match spec.status {
+                ProcessStatus::Running => deps::wait(&state, &spec).await,
+                _ => Ok(()),
+            };
+            let res = match res {
+                Ok(()) => state.clone().start(spec).await.map(drop),
+                Err(err) => Err(err),
+            };
+            let error = res.err().map(|err| err.to_string());
+
+            RestoreResponse { name, error }
+        });
+
+        responses.extend(future::join_all(futures).await);
+    }
 
-        let res = state.clone().start(spec).await;
-        let error = res.err().map(|err| err.to_string());
-
-        Ok::<_, Error>(RestoreResponse { name, error })
-    });
-
-    let responses = future::try_join_all(futures).await?;
     let response = Response::Restore(responses);
     let serialized = json::to_string(&response)?;
     conn.send(serialized).await?;
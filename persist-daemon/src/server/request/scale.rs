@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use futures::sink::SinkExt;
+use tokio::net::UnixStream;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use persist_core::error::Error;
+use persist_core::protocol::{Response, ScaleRequest};
+
+use crate::server::State;
+
+pub async fn handle(
+    state: Arc<State>,
+    conn: &mut Framed<UnixStream, LinesCodec>,
+    req: ScaleRequest,
+) -> Result<(), Error> {
+    let ScaleRequest { name, count } = req;
+    let scaled = state.scale(name, count).await?;
+
+    let response = Response::Scale(scaled);
+    let serialized = json::to_string(&response)?;
+    conn.send(serialized).await?;
+
+    Ok(())
+}
@@ -5,9 +5,11 @@ use futures::sink::SinkExt;
 use tokio::net::UnixStream;
 use tokio_util::codec::{Framed, LinesCodec};
 
-use persist_core::error::Error;
-use persist_core::protocol::{ProcessSpec, Response, StartRequest, StartResponse};
+use persist_core::error::{Error, PersistError};
+use persist_core::protocol::{ProcessSpec, ProcessStatus, Response, StartRequest, StartResponse};
 
+use crate::server::deps;
+use crate::server::state;
 use crate::server::State;
 
 pub async fn handle(
@@ -23,14 +25,65 @@ pub async fn handle(
         env: spec.env,
         cwd: spec.cwd,
         status: spec.status,
+        restart: spec.restart,
+        stop_signal: spec.stop_signal,
+        stop_timeout: spec.stop_timeout,
+        health_check: spec.health_check,
+        log_rotation: spec.log_rotation,
+        log_buffer: spec.log_buffer,
+        multiline: spec.multiline,
+        log_format: spec.log_format,
+        log_sinks: spec.log_sinks,
+        ansi: spec.ansi,
+        stdin: spec.stdin,
+        pty: spec.pty,
+        depends_on: spec.depends_on,
+        job: spec.job,
+        watch: spec.watch,
+        instances: spec.instances,
+        instance: None,
         created_at: now,
         pid_path: PathBuf::new(),
         stdout_path: PathBuf::new(),
         stderr_path: PathBuf::new(),
     };
 
-    //? start the process according to that spec
-    let info = state.start(spec).await?;
+    //? make sure it is valid before waiting for its dependencies.
+    state::validate_spec(&spec)?;
+
+    //? make sure it does not introduce a dependency cycle, and that its dependencies are ready.
+    let graph = state
+        .with_handles(|handles| {
+            let managed = handles.values().map(|handle| handle.spec());
+            deps::graph(managed.chain([&spec]))
+        })
+        .await;
+    deps::layers(&graph)?;
+    if let ProcessStatus::Running = spec.status {
+        deps::wait(&state, &spec).await?;
+    }
+
+    //? start the process according to that spec (as all of its replicas, if it has several instances).
+    let info = match spec.instances.as_ref() {
+        Some(instances) => {
+            let managed = state
+                .with_handles(|handles| {
+                    handles.contains_key(spec.name.as_str())
+                        || handles
+                            .values()
+                            .any(|handle| handle.spec().group() == spec.name)
+                })
+                .await;
+            if managed {
+                return Err(Error::from(PersistError::ProcessAlreadyExists));
+            }
+
+            let started = state.start_replicas(&spec, 0..instances.count).await?;
+            (started.into_iter().next())
+                .ok_or_else(|| Error::from(String::from("no instances to start")))?
+        }
+        None => state.start(spec).await?,
+    };
 
     let response = Response::Start(StartResponse { spec: info.into() });
     let serialized = json::to_string(&response)?;
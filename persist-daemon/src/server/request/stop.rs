@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::future;
 use futures::sink::SinkExt;
@@ -6,8 +7,9 @@ use tokio::net::UnixStream;
 use tokio_util::codec::{Framed, LinesCodec};
 
 use persist_core::error::Error;
-use persist_core::protocol::{Response, StopRequest, StopResponse};
+use persist_core::protocol::{self, Response, StopRequest, StopResponse};
 
+use crate::server::deps;
 use crate::server::State;
 
 pub async fn handle(
@@ -16,21 +18,31 @@ pub async fn handle(
     request: StopRequest,
 ) -> Result<(), Error> {
     let names = match request.filters {
-        Some(names) => names,
+        Some(names) => state.resolve(names).await,
         None => {
             state
                 .with_handles(|handles| handles.keys().cloned().collect())
                 .await
         }
     };
+    let state = &state;
 
-    let futures = names.into_iter().map(|name| async {
-        let res = state.stop(name.as_str()).await;
-        let error = res.err().map(|err| err.to_string());
-        StopResponse { name, error }
-    });
+    let signal = match request.signal {
+        Some(signal) => Some(protocol::parse_signal(signal.as_str())?),
+        None => None,
+    };
+    let timeout = request.timeout.map(Duration::from_millis);
 
-    let responses = future::join_all(futures).await;
+    //? processes are stopped before the ones they depend on.
+    let mut responses = Vec::with_capacity(names.len());
+    for layer in deps::managed_layers(state, names).await.into_iter().rev() {
+        let futures = layer.into_iter().map(|name| async move {
+            let res = state.stop(name.as_str(), signal, timeout).await;
+            let error = res.err().map(|err| err.to_string());
+            StopResponse { name, error }
+        });
+        responses.extend(future::join_all(futures).await);
+    }
     let response = Response::Stop(responses);
     let serialized = json::to_string(&response)?;
     conn.send(serialized).await?;
@@ -0,0 +1,64 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use cron::Schedule;
+
+use persist_core::error::Error;
+use persist_core::protocol::{JobSchedule, JobSpec};
+
+/// Parses a cron expression.
+///
+/// On top of the formats of the `cron` crate (which start with a seconds field),
+/// the standard 5-field expressions are accepted, and run at the start of the minute.
+pub fn parse(expr: &str) -> Result<Schedule, Error> {
+    let expr = expr.trim();
+    let expanded = match expr.split_whitespace().count() {
+        5 => format!("0 {}", expr),
+        _ => expr.to_string(),
+    };
+
+    Schedule::from_str(expanded.as_str()).map_err(|err| {
+        let reason = err.to_string();
+        let reason = reason.trim_end_matches('.');
+        Error::from(format!("invalid cron expression '{}': {}", expr, reason))
+    })
+}
+
+/// Makes sure that the schedule of a job is valid.
+pub fn validate(job: &JobSpec) -> Result<(), Error> {
+    match job.schedule {
+        JobSchedule::Once => Ok(()),
+        JobSchedule::Cron(ref expr) => parse(expr).map(drop),
+    }
+}
+
+/// Gets when a job is due to run next (strictly after the given time), if it runs on a cron schedule.
+pub fn next_run(
+    job: &JobSpec,
+    after: chrono::DateTime<chrono::Local>,
+) -> Option<chrono::DateTime<chrono::Local>> {
+    let JobSchedule::Cron(ref expr) = job.schedule else {
+        return None;
+    };
+
+    //? the schedule is validated before managing the process, so it cannot fail here.
+    parse(expr).ok()?.after(&after).next()
+}
+
+/// Gets the time left until the given instant (none if it already passed).
+pub fn until(instant: chrono::DateTime<chrono::Local>) -> Duration {
+    (instant - chrono::Local::now())
+        .to_std()
+        .unwrap_or_default()
+}
+
+/// Whether a job runs on a cron schedule (as opposed to once, or not being a job at all).
+pub fn is_scheduled(job: Option<&JobSpec>) -> bool {
+    matches!(
+        job,
+        Some(JobSpec {
+            schedule: JobSchedule::Cron(_),
+            ..
+        })
+    )
+}
@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::PathBuf;
+
+use flate2::read::GzDecoder;
+use regex::{Regex, RegexBuilder};
+
+use persist_core::error::Error;
+use persist_core::protocol::{LogEntry, LogFormat, LogSearch, LogStreamSource};
+
+use crate::server::codec;
+use crate::server::filter::EntryFilter;
+use crate::server::structured;
+
+/// A compiled log search.
+#[derive(Debug, Clone)]
+pub struct Search {
+    regex: Regex,
+    before: usize,
+    after: usize,
+}
+
+impl Search {
+    pub fn new(search: &LogSearch) -> Result<Self, Error> {
+        let pattern = match search.literal {
+            true => regex::escape(search.pattern.as_str()),
+            false => search.pattern.clone(),
+        };
+        let regex = RegexBuilder::new(pattern.as_str())
+            .case_insensitive(search.ignore_case)
+            .build()
+            .map_err(|err| format!("invalid search pattern: {}", err))?;
+
+        Ok(Self {
+            regex,
+            before: search.before,
+            after: search.after,
+        })
+    }
+
+    /// Creates a matcher, to feed the entries of a single stream to.
+    pub fn matcher(&self) -> Matcher {
+        Matcher {
+            search: self.clone(),
+            before: VecDeque::new(),
+            after: 0,
+        }
+    }
+}
+
+/// The state of a search through a single stream of log entries.
+pub struct Matcher {
+    search: Search,
+    /// The latest non-matching entries, which may precede the next match.
+    before: VecDeque<LogEntry>,
+    /// The number of entries still to include after the last match.
+    after: usize,
+}
+
+impl Matcher {
+    /// Feeds the next entry of the stream, returning the entries to output because of it.
+    ///
+    /// If it matches, these are its preceding context followed by the entry itself.
+    pub fn feed(&mut self, mut entry: LogEntry) -> Vec<LogEntry> {
+        if self.search.regex.is_match(entry.msg.as_str()) {
+            entry.context = false;
+            self.after = self.search.after;
+            let mut entries: Vec<_> = self.before.drain(..).collect();
+            entries.push(entry);
+            return entries;
+        }
+
+        entry.context = true;
+        if self.after > 0 {
+            self.after -= 1;
+            return vec![entry];
+        }
+
+        if self.search.before > 0 {
+            if self.before.len() == self.search.before {
+                self.before.pop_front();
+            }
+            self.before.push_back(entry);
+        }
+
+        Vec::new()
+    }
+}
+
+/// Searches through log files (given from oldest to newest, possibly gzip-compressed),
+/// among the entries meeting the given criteria.
+///
+/// Returns the last `count` matches, each grouped with its context.
+pub async fn search_files(
+    search: &Search,
+    paths: Vec<PathBuf>,
+    (name, source, format): (&str, LogStreamSource, LogFormat),
+    count: usize,
+    filter: &EntryFilter,
+) -> Result<Vec<Vec<LogEntry>>, Error> {
+    let mut matcher = search.matcher();
+    let name = name.to_string();
+    let filter = filter.clone();
+
+    //? files are read synchronously, since compressed ones can only be read sequentially.
+    tokio::task::spawn_blocking(move || {
+        let mut groups: VecDeque<Vec<LogEntry>> = VecDeque::new();
+        for path in paths {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(Error::from(err)),
+            };
+            let reader: Box<dyn Read> = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("gz") => Box::new(GzDecoder::new(file)),
+                _ => Box::new(file),
+            };
+            let mut reader = BufReader::new(reader);
+
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+
+                let line = String::from_utf8_lossy(&line);
+                let (timestamp, msg) = codec::decode_line(&line);
+                //? lines are in chronological order, so no later line can be early enough either.
+                let until = filter.until;
+                if until.is_some_and(|until| timestamp.is_some_and(|timestamp| timestamp > until)) {
+                    return Ok(groups.into_iter().collect());
+                }
+
+                let entry = LogEntry {
+                    source,
+                    timestamp,
+                    name: name.clone(),
+                    context: false,
+                    structured: structured::parse(format, &msg),
+                    msg: msg.into_owned(),
+                };
+                if !filter.matches(&entry) {
+                    continue;
+                }
+
+                let entries = matcher.feed(entry);
+                match entries.last() {
+                    Some(last) if !last.context => {
+                        groups.push_back(entries);
+                        if groups.len() > count {
+                            groups.pop_front();
+                        }
+                    }
+                    Some(_) => {
+                        if let Some(group) = groups.back_mut() {
+                            group.extend(entries);
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        Ok(groups.into_iter().collect())
+    })
+    .await
+    .map_err(|err| Error::from(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    fn search(pattern: &str, before: usize, after: usize) -> Search {
+        Search::new(&LogSearch {
+            before,
+            after,
+            pattern: pattern.to_string(),
+            literal: false,
+            ignore_case: false,
+        })
+        .unwrap()
+    }
+
+    fn entry(msg: &str) -> LogEntry {
+        LogEntry {
+            name: String::from("app"),
+            source: LogStreamSource::Stdout,
+            timestamp: None,
+            context: false,
+            msg: msg.to_string(),
+            structured: None,
+        }
+    }
+
+    /// Feeds lines to a matcher, returning the output entries (with `>` marking the matches).
+    fn feed(search: &Search, lines: &[&str]) -> Vec<String> {
+        let mut matcher = search.matcher();
+        let entries = lines.iter().flat_map(|line| matcher.feed(entry(line)));
+        let render = |entry: LogEntry| match entry.context {
+            true => format!("  {}", entry.msg),
+            false => format!("> {}", entry.msg),
+        };
+        entries.map(render).collect()
+    }
+
+    #[test]
+    fn matches_without_context() {
+        let search = search("err", 0, 0);
+        let output = feed(&search, &["ok", "err 1", "ok", "err 2"]);
+        assert_eq!(output, vec!["> err 1", "> err 2"]);
+    }
+
+    #[test]
+    fn context_around_matches() {
+        let search = search("err", 2, 1);
+        let output = feed(&search, &["a", "b", "c", "err", "d", "e", "f"]);
+        assert_eq!(output, vec!["  b", "  c", "> err", "  d"]);
+    }
+
+    #[test]
+    fn overlapping_contexts_are_merged() {
+        //? "b" is both after the first match and before the second one, but only output once.
+        let search = search("err", 2, 2);
+        let output = feed(&search, &["a", "err 1", "b", "err 2", "c", "d", "e"]);
+        assert_eq!(
+            output,
+            vec!["  a", "> err 1", "  b", "> err 2", "  c", "  d"]
+        );
+    }
+
+    #[test]
+    fn before_context_is_not_taken_from_previous_matches() {
+        let search = search("err", 3, 0);
+        let output = feed(&search, &["err 1", "a", "err 2"]);
+        assert_eq!(output, vec!["> err 1", "  a", "> err 2"]);
+    }
+
+    #[test]
+    fn literal_and_case_insensitive_patterns() {
+        let literal = Search::new(&LogSearch {
+            pattern: String::from("a.b"),
+            literal: true,
+            ignore_case: true,
+            before: 0,
+            after: 0,
+        })
+        .unwrap();
+        let output = feed(&literal, &["axb", "A.B"]);
+        assert_eq!(output, vec!["> A.B"]);
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let invalid = LogSearch {
+            pattern: String::from("(unclosed"),
+            literal: false,
+            ignore_case: false,
+            before: 0,
+            after: 0,
+        };
+        assert!(Search::new(&invalid).is_err());
+    }
+
+    fn write_lines(path: &Path, lines: &[&str], compressed: bool) {
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        match compressed {
+            true => {
+                let mut encoder =
+                    GzEncoder::new(File::create(path).unwrap(), Compression::default());
+                encoder.write_all(contents.as_bytes()).unwrap();
+                encoder.finish().unwrap();
+            }
+            false => std::fs::write(path, contents).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn search_through_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("persist-search-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let oldest = dir.join("app-out.log.2.gz");
+        let rotated = dir.join("app-out.log.1");
+        let current = dir.join("app-out.log");
+        write_lines(&oldest, &["err 1", "a", "err 2"], true);
+        write_lines(&rotated, &["b", "err 3"], false);
+        write_lines(&current, &["c", "d", "err 4"], false);
+
+        //? the missing files are skipped, and the context of a match goes on across files.
+        let paths = vec![dir.join("app-out.log.3"), oldest, rotated, current];
+        let origin = ("app", LogStreamSource::Stdout, LogFormat::Text);
+        let filter = EntryFilter::default();
+        let groups = search_files(&search("err", 0, 1), paths, origin, 3, &filter)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let groups: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|group| group.iter().map(|entry| entry.msg.as_str()).collect())
+            .collect();
+        assert_eq!(
+            groups,
+            vec![vec!["err 2", "b"], vec!["err 3", "c"], vec!["err 4"]]
+        );
+    }
+}
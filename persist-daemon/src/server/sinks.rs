@@ -0,0 +1,206 @@
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, SecondsFormat};
+use tokio::net::UnixDatagram;
+
+use persist_core::protocol::{self, LogLevel, LogSink, LogStreamSource};
+
+/// The maximum length of the application name of a syslog message (as per RFC 5424).
+const MAX_APP_NAME_LEN: usize = 48;
+
+/// Gets the syslog severity of a log entry, from its level (if it is structured) or its stream.
+fn severity(source: LogStreamSource, level: Option<LogLevel>) -> u8 {
+    match (level, source) {
+        (Some(LogLevel::Fatal), _) => 2,
+        (Some(LogLevel::Error), _) => 3,
+        (Some(LogLevel::Warn), _) => 4,
+        (Some(LogLevel::Info), _) => 6,
+        (Some(LogLevel::Trace | LogLevel::Debug), _) => 7,
+        (None, LogStreamSource::Stdout) => 6,
+        (None, LogStreamSource::Stderr) => 3,
+    }
+}
+
+/// Sanitizes a syslog header field, which must be made of printable ASCII characters only.
+fn header_field(value: &str, max_len: usize) -> String {
+    let value: String = value
+        .chars()
+        .map(|ch| if ch.is_ascii_graphic() { ch } else { '_' })
+        .take(max_len)
+        .collect();
+    match value.is_empty() {
+        true => String::from("-"),
+        false => value,
+    }
+}
+
+/// Appends a field to a message of the journald native protocol.
+///
+/// Values spanning multiple lines are length-prefixed, as required by the protocol.
+fn journal_field(payload: &mut Vec<u8>, key: &str, value: &str) {
+    payload.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        payload.push(b'\n');
+        payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        payload.push(b'=');
+    }
+    payload.extend_from_slice(value.as_bytes());
+    payload.push(b'\n');
+}
+
+enum Format {
+    /// RFC 5424 syslog messages.
+    Syslog {
+        facility: u8,
+        app_name: String,
+        hostname: String,
+    },
+    /// The journald native protocol.
+    Journald,
+}
+
+struct Destination {
+    format: Format,
+    path: PathBuf,
+    socket: UnixDatagram,
+    /// Whether the last attempt to send an entry failed (to only report the first of consecutive failures).
+    failing: bool,
+    /// The number of entries dropped since the last one sent, because the socket's buffer was full.
+    dropped: u64,
+}
+
+/// The additional destinations of the logs of one of the output streams of a process.
+pub struct LogSinks {
+    name: String,
+    pid: usize,
+    source: LogStreamSource,
+    destinations: Vec<Destination>,
+}
+
+impl LogSinks {
+    /// Prepares the sending of log entries to the given destinations.
+    ///
+    /// The sockets are only written to when sending entries, so that they can be (re)created later.
+    pub fn open(sinks: &[LogSink], name: &str, pid: usize, source: LogStreamSource) -> Self {
+        let hostname = nix::unistd::gethostname()
+            .ok()
+            .and_then(|hostname| hostname.into_string().ok())
+            .unwrap_or_default();
+
+        let destinations = sinks
+            .iter()
+            .filter_map(|sink| {
+                let (format, path) = match sink {
+                    LogSink::Syslog {
+                        socket,
+                        facility,
+                        tag,
+                    } => {
+                        //? the facility is validated before managing the process.
+                        let facility = protocol::parse_syslog_facility(facility).unwrap_or(1);
+                        let app_name = tag.as_deref().unwrap_or(name);
+                        let format = Format::Syslog {
+                            facility,
+                            app_name: header_field(app_name, MAX_APP_NAME_LEN),
+                            hostname: header_field(hostname.as_str(), 255),
+                        };
+                        (format, socket.clone())
+                    }
+                    LogSink::Journald { socket } => (Format::Journald, socket.clone()),
+                };
+
+                match UnixDatagram::unbound() {
+                    Ok(socket) => Some(Destination {
+                        format,
+                        path,
+                        socket,
+                        failing: false,
+                        dropped: 0,
+                    }),
+                    Err(err) => {
+                        eprintln!("could not create socket for '{}': {}", sink, err);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            pid,
+            source,
+            destinations,
+            name: name.to_string(),
+        }
+    }
+
+    /// Sends a log entry to every destination.
+    ///
+    /// This never waits for a destination to be ready to receive it: the entry is dropped for those that are not,
+    /// for a slow log collector not to hold up the forwarding of the process' output.
+    pub fn send(&mut self, timestamp: &DateTime<Local>, msg: &str, level: Option<LogLevel>) {
+        let severity = severity(self.source, level);
+        for destination in self.destinations.iter_mut() {
+            let payload = match &destination.format {
+                Format::Syslog {
+                    facility,
+                    app_name,
+                    hostname,
+                } => {
+                    let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::Micros, false);
+                    let message = format!(
+                        "<{}>1 {} {} {} {} - - {}",
+                        facility * 8 + severity,
+                        timestamp,
+                        hostname,
+                        app_name,
+                        self.pid,
+                        msg,
+                    );
+                    message.into_bytes()
+                }
+                Format::Journald => {
+                    let stream = match self.source {
+                        LogStreamSource::Stdout => "stdout",
+                        LogStreamSource::Stderr => "stderr",
+                    };
+                    let mut payload = Vec::new();
+                    journal_field(&mut payload, "MESSAGE", msg);
+                    journal_field(&mut payload, "PRIORITY", &severity.to_string());
+                    journal_field(&mut payload, "SYSLOG_IDENTIFIER", &self.name);
+                    journal_field(&mut payload, "SYSLOG_PID", &self.pid.to_string());
+                    journal_field(&mut payload, "PERSIST_PROCESS", &self.name);
+                    journal_field(&mut payload, "PERSIST_STREAM", stream);
+                    payload
+                }
+            };
+
+            match destination.socket.try_send_to(&payload, &destination.path) {
+                Ok(_) => {
+                    destination.failing = false;
+                    if destination.dropped > 0 {
+                        eprintln!(
+                            "dropped {} log entries of '{}' that could not be sent to {} in time",
+                            destination.dropped,
+                            self.name,
+                            destination.path.display(),
+                        );
+                        destination.dropped = 0;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => destination.dropped += 1,
+                Err(err) if !destination.failing => {
+                    destination.failing = true;
+                    eprintln!(
+                        "could not send logs of '{}' to {}: {}",
+                        self.name,
+                        destination.path.display(),
+                        err,
+                    );
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
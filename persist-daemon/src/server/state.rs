@@ -1,28 +1,371 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures::future;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
 use sysinfo::{PidExt, ProcessExt, System, SystemExt};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
-use persist_core::daemon::{self, LOGS_DIR, PIDS_DIR};
+use persist_core::daemon::{self, LOGS_DIR, PIDS_DIR, STATE_FILE};
 use persist_core::error::{Error, PersistError};
 use persist_core::protocol::{
-    ListResponse, LogEntry, LogStreamSource, ProcessInfo, ProcessSpec, ProcessStatus,
+    self, default_log_buffer, KillResponse, LifecycleEvent, ListResponse, LogEntry, LogFormat,
+    LogSink, LogStreamSource, LogsRequest, LogsResponse, ProcessInfo, ProcessSpec, ProcessStatus,
+    ScaleResponse, StopResponse,
 };
 
-use crate::server::handle::ProcessHandle;
+use crate::server::codec;
+use crate::server::deps;
+use crate::server::filter::EntryFilter;
+use crate::server::handle::{ExitFuture, LogChannels, ProcessHandle, Run, Stopping};
+use crate::server::health;
+use crate::server::multiline::Grouper;
+use crate::server::replicas;
+use crate::server::schedule;
+use crate::server::search::{self, Search};
+use crate::server::structured;
+use crate::server::tail::ReverseLines;
+use crate::server::watcher::Watcher;
 
 struct Inner {
     system: System,
     processes: HashMap<String, ProcessHandle>,
+    /// The log channels of every managed or followed process, by name.
+    channels: HashMap<String, LogChannels>,
+    /// Whether the daemon is shutting down (processes are neither restarted nor persisted anymore).
+    shutting_down: bool,
+}
+
+impl Inner {
+    /// Makes sure that the daemon is not shutting down, as processes started from then on would never get stopped.
+    fn ensure_running(&self) -> Result<(), Error> {
+        match self.shutting_down {
+            true => Err(Error::from(String::from("the daemon is shutting down"))),
+            false => Ok(()),
+        }
+    }
+
+    /// Gets the names of the managed replicas of a process, ordered by their index.
+    fn members(&self, group: &str) -> Vec<String> {
+        let mut members: Vec<(usize, &String)> = self
+            .processes
+            .iter()
+            .filter(|(_, handle)| handle.spec().group() == group)
+            .filter_map(|(name, handle)| Some((handle.spec().instance?, name)))
+            .collect();
+        members.sort();
+        members.into_iter().map(|(_, name)| name.clone()).collect()
+    }
+
+    /// Records the number of replicas of a process in the specifications of the given replicas.
+    fn set_instances(&mut self, members: &[String], count: usize) {
+        for name in members.iter() {
+            if let Some(handle) = self.processes.get_mut(name.as_str()) {
+                if let Some(instances) = handle.spec.instances.as_mut() {
+                    instances.count = count;
+                }
+            }
+        }
+    }
+
+    /// Gets the specifications of every managed process, along with their current status.
+    fn snapshot(&self) -> Vec<ProcessSpec> {
+        self.processes
+            .values()
+            .map(|handle| {
+                let mut spec = handle.spec().clone();
+                spec.status = handle.status();
+                spec
+            })
+            .collect()
+    }
+
+    /// Gets the log channels for a new handle of a process,
+    /// reusing the ones of a previous handle (with the same name) if they are still being followed.
+    fn new_channels(&mut self, spec: &ProcessSpec) -> LogChannels {
+        match self.channels.get(spec.name.as_str()) {
+            Some(channels) if channels.is_followed() => channels.clone(),
+            _ => {
+                let channels = LogChannels::new(spec.log_buffer);
+                self.channels.insert(spec.name.clone(), channels.clone());
+                channels
+            }
+        }
+    }
+
+    /// Recreates the log channels of a process if their capacity does not match its specification anymore,
+    /// unless they are being followed (the followers would otherwise miss what comes next).
+    fn refresh_channels(&mut self, name: &str) {
+        let Some(handle) = self.processes.get_mut(name) else {
+            return;
+        };
+        let log_buffer = handle.spec().log_buffer;
+        if handle.channels.is_followed() || handle.channels.fits(log_buffer) {
+            return;
+        }
+
+        let channels = LogChannels::new(log_buffer);
+        handle.channels = channels.clone();
+        self.channels.insert(name.to_string(), channels);
+    }
+
+    /// Gets the log channels to follow a process by name, even if no such process is managed (yet).
+    fn follow_channels(&mut self, name: &str) -> LogChannels {
+        self.channels
+            .entry(name.to_string())
+            .or_insert_with(|| LogChannels::new(default_log_buffer()))
+            .clone()
+    }
+
+    /// Forgets the log channels of the processes that are neither managed nor followed anymore.
+    fn prune_channels(&mut self) {
+        let processes = &self.processes;
+        self.channels
+            .retain(|name, channels| processes.contains_key(name) || channels.is_followed());
+    }
+
+    /// Subscribes to the live logs (and events) of the given processes (or all of them).
+    fn follow(
+        &mut self,
+        filters: Option<Vec<String>>,
+        source_filter: Option<LogStreamSource>,
+        search: Option<&Search>,
+        filter: &EntryFilter,
+        resume_on_lag: bool,
+    ) -> Result<Vec<BoxStream<'static, LogsResponse>>, Error> {
+        //? processes are followed by name, so that it goes on across them being stopped, restarted or even recreated.
+        let names: Vec<String> = match filters {
+            Some(names) => names,
+            None => self.processes.keys().cloned().collect(),
+        };
+        let logs_dir = daemon::home_dir()?.join(LOGS_DIR);
+        let mut followed = Vec::new();
+        for name in names {
+            let channels = self.follow_channels(name.as_str());
+            let handle = self.processes.get(name.as_str());
+            let format = handle.map(|it| it.spec().log_format).unwrap_or_default();
+            let ansi = handle.map(|it| it.spec().ansi).unwrap_or_default();
+            followed.push(channels.events().boxed());
+
+            let streams = [
+                (
+                    LogStreamSource::Stdout,
+                    "out",
+                    handle.map(|it| it.stdout_file()),
+                ),
+                (
+                    LogStreamSource::Stderr,
+                    "err",
+                    handle.map(|it| it.stderr_file()),
+                ),
+            ];
+            for (source, suffix, path) in streams {
+                if source_filter.is_some_and(|filter| filter != source) {
+                    continue;
+                }
+                let path = match path {
+                    Some(path) => path.to_path_buf(),
+                    None => logs_dir.join(format!("{}-{}.log", name, suffix)),
+                };
+
+                //? each stream gets its own matcher, so that context lines never span across streams.
+                let mut matcher = search.map(Search::matcher);
+                let filter = filter.clone();
+                let stream = channels
+                    .subscribe((name.clone(), source, format), ansi, path, resume_on_lag)
+                    .filter(move |item| {
+                        let keep = match item {
+                            LogsResponse::Entry(entry) => filter.matches(entry),
+                            _ => true,
+                        };
+                        future::ready(keep)
+                    })
+                    .flat_map(move |item| {
+                        let items = match (item, matcher.as_mut()) {
+                            (LogsResponse::Entry(entry), Some(matcher)) => matcher
+                                .feed(entry)
+                                .into_iter()
+                                .map(LogsResponse::Entry)
+                                .collect(),
+                            (item, _) => vec![item],
+                        };
+                        futures::stream::iter(items)
+                    });
+                followed.push(stream.boxed());
+            }
+        }
+
+        Ok(followed)
+    }
 }
 
 pub struct State {
     inner: Mutex<Inner>,
+    /// Notified whenever the managed processes change, to get their specifications persisted.
+    changed: Notify,
+    /// Notified once the daemon is ready to terminate.
+    terminated: Notify,
+}
+
+/// Finds the still-running process that a previous daemon left behind for the given specification, if any.
+///
+/// The process is looked up from its PID file, and must still run the expected command
+/// (to not mistake an unrelated process that reused the same PID for it).
+async fn find_orphan(system: &mut System, spec: &ProcessSpec) -> Option<(Pid, Instant)> {
+    let contents = tokio::fs::read_to_string(&spec.pid_path).await.ok()?;
+    let pid = contents.trim().parse::<u32>().ok()?;
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+
+    if !system.refresh_process(sys_pid) {
+        return None;
+    }
+    let process = system.process(sys_pid)?;
+    if let sysinfo::ProcessStatus::Zombie = process.status() {
+        return None;
+    }
+
+    let same_cmd = process.cmd() == spec.cmd.as_slice();
+    let same_exe = spec
+        .cmd
+        .first()
+        .is_some_and(|cmd| Path::new(cmd).file_name() == process.exe().file_name());
+    if !same_cmd && !same_exe {
+        return None;
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let uptime = Duration::from_secs(now.saturating_sub(process.start_time()));
+    let started_at = Instant::now()
+        .checked_sub(uptime)
+        .unwrap_or_else(Instant::now);
+
+    Some((Pid::from_raw(pid as _), started_at))
+}
+
+/// Reads the last lines of a log file (among the ones meeting the given criteria), as log entries.
+async fn read_entries(
+    path: &Path,
+    (name, source, format): (&str, LogStreamSource, LogFormat),
+    lines: usize,
+    filter: &EntryFilter,
+) -> Result<Vec<LogEntry>, Error> {
+    let mut reader = ReverseLines::open(path).await?;
+    let mut entries = Vec::new();
+    while entries.len() < lines {
+        let Some(line) = reader.prev_line().await? else {
+            break;
+        };
+
+        let (timestamp, msg) = codec::decode_line(&line);
+        let entry = LogEntry {
+            source,
+            timestamp,
+            name: name.to_string(),
+            context: false,
+            structured: structured::parse(format, &msg),
+            msg: msg.into_owned(),
+        };
+
+        //? lines are in chronological order, so no earlier line can be recent enough either.
+        let since = filter.since;
+        if since.is_some_and(|since| entry.timestamp.is_none_or(|timestamp| timestamp < since)) {
+            break;
+        }
+
+        if filter.matches(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries.reverse();
+
+    Ok(entries)
+}
+
+/// Merges the backlogs of several streams into a single timeline, keeping only the last `count` groups of entries
+/// (which are single entries, or search matches along with their context).
+fn merge_backlogs(backlogs: Vec<Vec<Vec<LogEntry>>>, count: usize) -> Vec<LogEntry> {
+    let mut groups: Vec<Vec<LogEntry>> = backlogs.into_iter().flatten().collect();
+    groups.sort_by_key(|group| {
+        let matched = group.iter().find(|entry| !entry.context);
+        matched.and_then(|entry| entry.timestamp)
+    });
+    let groups = groups.split_off(groups.len().saturating_sub(count));
+    let mut backlog: Vec<LogEntry> = groups.into_iter().flatten().collect();
+    backlog.sort_by_key(|entry| entry.timestamp);
+    backlog
+}
+
+/// Makes sure that a process specification is valid, before managing the process.
+///
+/// Specifications do not only come from start requests, but also from dumps and the state file.
+pub fn validate_spec(spec: &ProcessSpec) -> Result<(), Error> {
+    if spec.cmd.is_empty() {
+        return Err(Error::from(String::from("the command cannot be empty")));
+    }
+    //? the stop signal must be one that can be sent.
+    protocol::parse_signal(spec.stop_signal.as_str())?;
+    //? same for the multiline pattern and the syslog facilities.
+    if let Some(multiline) = spec.multiline.as_ref() {
+        Grouper::new(multiline)?;
+    }
+    for sink in spec.log_sinks.iter() {
+        if let LogSink::Syslog { facility, .. } = sink {
+            protocol::parse_syslog_facility(facility)?;
+        }
+    }
+    //? and for the size of the log buffers, which get allocated upfront.
+    if spec.log_buffer > protocol::MAX_LOG_BUFFER {
+        return Err(Error::from(format!(
+            "the log buffer cannot hold more than {} lines",
+            protocol::MAX_LOG_BUFFER
+        )));
+    }
+    //? and for the interval of health checks, which would otherwise run continuously.
+    if spec
+        .health_check
+        .as_ref()
+        .is_some_and(|check| check.interval == 0)
+    {
+        return Err(Error::from(String::from(
+            "the health check interval must be greater than 0",
+        )));
+    }
+    //? and for the schedule of jobs.
+    if let Some(job) = spec.job.as_ref() {
+        schedule::validate(job)?;
+    }
+    //? and for the number of instances.
+    if spec
+        .instances
+        .as_ref()
+        .is_some_and(|instances| instances.count == 0)
+    {
+        return Err(Error::from(String::from(
+            "the number of instances must be at least 1",
+        )));
+    }
+
+    Ok(())
+}
+
+/// Atomically writes process specifications into the state file, so that it is never left half-written.
+async fn save_specs(specs: &[ProcessSpec]) -> Result<(), Error> {
+    let tmp_path = format!("{}.tmp", STATE_FILE);
+    let serialized = json::to_string_pretty(specs)?;
+    tokio::fs::write(&tmp_path, serialized).await?;
+    tokio::fs::rename(&tmp_path, STATE_FILE).await?;
+    Ok(())
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
 }
 
 impl State {
@@ -32,8 +375,264 @@ impl State {
             inner: Mutex::new(Inner {
                 system: System::new(),
                 processes: HashMap::default(),
+                channels: HashMap::default(),
+                shutting_down: false,
             }),
+            changed: Notify::new(),
+            terminated: Notify::new(),
+        }
+    }
+
+    /// Signals that the managed processes changed, so that their specifications get persisted again.
+    fn changed(&self) {
+        self.changed.notify_one();
+    }
+
+    /// Persists the specifications of the managed processes into the state file, every time they change.
+    pub async fn persist_changes(self: Arc<Self>) {
+        loop {
+            self.changed.notified().await;
+
+            //? the lock is held while writing, so that snapshots are always written in order.
+            let locked = self.inner.lock().await;
+            if locked.shutting_down {
+                return;
+            }
+
+            if let Err(err) = save_specs(&locked.snapshot()).await {
+                eprintln!("could not persist process specifications: {}", err);
+            }
+        }
+    }
+
+    /// Reloads previously persisted process specifications, and starts again those that were running.
+    pub async fn resurrect(self: Arc<Self>) -> Result<(), Error> {
+        let contents = match tokio::fs::read(STATE_FILE).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(Error::from(err)),
+        };
+        let mut specs: Vec<ProcessSpec> = json::from_slice(contents.as_slice())?;
+
+        //? the state file may have been edited by hand, so invalid specifications are left out.
+        specs.retain(|spec| match validate_spec(spec) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("could not resurrect '{}': {}", spec.name, err);
+                false
+            }
+        });
+
+        //? processes are brought back after the ones they depend on.
+        let order = deps::order(&deps::graph(specs.iter())).unwrap_or_default();
+        specs.sort_by_key(|spec| order.iter().position(|name| *name == spec.name));
+
+        //? walking the watched directories may take a while, so it is done before taking the lock.
+        let mut watchers = Vec::with_capacity(specs.len());
+        for spec in specs.iter() {
+            let watcher = match spec.watch.as_ref() {
+                Some(watch) => Some(Watcher::build(watch, &spec.cwd).await),
+                None => None,
+            };
+            watchers.push(watcher);
+        }
+
+        let mut deferred = Vec::new();
+        let mut locked = self.inner.lock().await;
+        let locked = &mut *locked;
+        for (spec, watcher) in specs.into_iter().zip(watchers) {
+            if locked.processes.contains_key(spec.name.as_str()) {
+                continue;
+            }
+
+            //? the directories may have been removed in the meantime.
+            for path in [&spec.pid_path, &spec.stdout_path, &spec.stderr_path] {
+                if let Some(parent) = path.parent() {
+                    let _ = tokio::fs::create_dir_all(parent).await;
+                }
+            }
+
+            let name = spec.name.clone();
+            let status = spec.status.clone();
+            let channels = locked.new_channels(&spec);
+            let handle = ProcessHandle::new(spec, channels);
+            let handle = locked.processes.entry(name.clone()).or_insert(handle);
+
+            match watcher.transpose() {
+                Ok(watcher) => self.clone().watch_files(handle, watcher),
+                Err(err) => eprintln!("could not watch the files of '{}': {}", name, err),
+            }
+
+            //? cron jobs wait for their next run, unless one was going on (which may have survived as well).
+            let scheduled = schedule::is_scheduled(handle.spec().job.as_ref());
+            if scheduled && matches!(status, ProcessStatus::Running | ProcessStatus::Scheduled) {
+                self.clone().schedule(handle);
+            }
+
+            if let ProcessStatus::Running = status {
+                //? the process may have survived the previous daemon, in which case we take it back
+                //? (unless it ran in a pseudo-terminal, which got closed along with that daemon).
+                let orphan = match handle.spec().pty {
+                    Some(_) => None,
+                    None => find_orphan(&mut locked.system, handle.spec()).await,
+                };
+                let outcome = match orphan {
+                    Some((pid, started_at)) => handle.adopt(pid, started_at).await,
+                    None if scheduled => continue,
+                    //? processes with dependencies get started once those are ready, without holding up the others
+                    //? (and after the ones they depend on, which may also be waiting for theirs).
+                    None if !handle.spec().depends_on.is_empty() => {
+                        deferred.push(handle.spec().clone());
+                        continue;
+                    }
+                    None => handle.start().await,
+                };
+
+                match outcome {
+                    Ok(future) => {
+                        let pid = handle.pid().unwrap();
+                        self.clone().watch(name, pid, future);
+                    }
+                    Err(err) => {
+                        eprintln!("could not resurrect '{}': {}", name, err);
+                    }
+                }
+            }
+        }
+
+        self.changed();
+
+        tokio::spawn(self.clone().resurrect_deferred(deferred));
+
+        Ok(())
+    }
+
+    /// Starts the resurrected processes that have dependencies, layer by layer, once these are ready.
+    async fn resurrect_deferred(self: Arc<Self>, specs: Vec<ProcessSpec>) {
+        for layer in deps::spec_layers(specs) {
+            let futures = layer
+                .into_iter()
+                .map(|spec| self.clone().resurrect_when_ready(spec));
+            future::join_all(futures).await;
+        }
+    }
+
+    /// Starts a resurrected process once the processes it depends on are ready.
+    async fn resurrect_when_ready(self: Arc<Self>, spec: ProcessSpec) {
+        let outcome = async {
+            deps::wait(&self, &spec).await?;
+
+            let mut locked = self.inner.lock().await;
+            locked.ensure_running()?;
+            let handle = locked
+                .processes
+                .get_mut(spec.name.as_str())
+                .ok_or(PersistError::ProcessNotFound)?;
+
+            //? it may have been started on request in the meantime.
+            if handle.process.is_some() {
+                return Ok(());
+            }
+
+            let future = handle.start().await;
+            self.changed();
+            let future = future?;
+
+            let pid = handle.pid().unwrap();
+            self.clone().watch(spec.name.clone(), pid, future);
+
+            Ok::<_, Error>(())
+        };
+
+        if let Err(err) = outcome.await {
+            eprintln!("could not resurrect '{}': {}", spec.name, err);
+        }
+    }
+
+    /// Stops every managed process (or leaves them running, if requested), in preparation of the daemon shutting down.
+    ///
+    /// The persisted specifications keep the statuses from before the shutdown,
+    /// so that the next daemon brings back the same processes.
+    pub async fn shutdown(&self, keep_processes: bool) -> KillResponse {
+        let mut stopped = Vec::new();
+        let mut kept = Vec::new();
+
+        let layers = {
+            let mut locked = self.inner.lock().await;
+            let locked = &mut *locked;
+            locked.shutting_down = true;
+
+            if let Err(err) = save_specs(&locked.snapshot()).await {
+                eprintln!("could not persist process specifications: {}", err);
+            }
+
+            //? pseudo-terminals get closed along with the daemon, so the processes using one cannot be kept.
+            let keeps = |handle: &ProcessHandle| keep_processes && handle.spec().pty.is_none();
+
+            for (name, handle) in locked.processes.iter_mut() {
+                handle.unschedule();
+                handle.unwatch();
+
+                if keeps(handle) {
+                    if let Some(task) = handle.pending_restart.take() {
+                        task.abort();
+                    }
+                    if handle.process.is_some() {
+                        kept.push(name.clone());
+                    }
+                }
+            }
+
+            let graph = deps::graph(
+                (locked.processes.values())
+                    .filter(|handle| !keeps(handle))
+                    .map(|handle| handle.spec()),
+            );
+            deps::layers(&graph).unwrap_or_else(|_| vec![graph.into_keys().collect()])
+        };
+
+        //? processes are stopped before the ones they depend on.
+        for layer in layers.into_iter().rev() {
+            let mut stopping = Vec::new();
+            {
+                let mut locked = self.inner.lock().await;
+                for name in layer {
+                    let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                        continue;
+                    };
+                    match handle.begin_stop(None, None) {
+                        Ok(Some(process)) => stopping.push((name, process)),
+                        Ok(None) => {}
+                        Err(err) => stopped.push(StopResponse {
+                            name,
+                            error: Some(err.to_string()),
+                        }),
+                    }
+                }
+            }
+
+            //? waiting for the processes also lets their log forwarders write out what remains of their output.
+            let futures = stopping.into_iter().map(|(name, process)| async move {
+                let error = process.wait().await.err().map(|err| err.to_string());
+                StopResponse { name, error }
+            });
+            stopped.extend(future::join_all(futures).await);
         }
+
+        stopped.sort_by(|a, b| a.name.cmp(&b.name));
+        kept.sort();
+
+        KillResponse { stopped, kept }
+    }
+
+    /// Signals that the daemon is ready to terminate.
+    pub fn terminate(&self) {
+        self.terminated.notify_one();
+    }
+
+    /// Waits until the daemon is ready to terminate.
+    pub async fn terminated(&self) {
+        self.terminated.notified().await;
     }
 
     /// Gets the process specification associated with the given name.
@@ -46,6 +645,27 @@ impl State {
             .ok_or_else(|| Error::from(PersistError::ProcessNotFound))
     }
 
+    /// Resolves names into the names of managed processes, where the name of a process running as several replicas
+    /// stands for all of its replicas.
+    ///
+    /// Unknown names are kept as they are (to get reported as such).
+    pub async fn resolve(&self, names: Vec<String>) -> Vec<String> {
+        let locked = self.inner.lock().await;
+        let mut resolved = Vec::with_capacity(names.len());
+        for name in names {
+            let members = match locked.processes.contains_key(name.as_str()) {
+                true => Vec::new(),
+                false => locked.members(name.as_str()),
+            };
+            match members.is_empty() {
+                true => resolved.push(name),
+                false => resolved.extend(members),
+            }
+        }
+
+        resolved
+    }
+
     /// Executes a closure and provides it every process handles.
     ///
     /// This closure is executed while holding a lock, so avoid calling other methods on `State` inside that closure.
@@ -127,11 +747,15 @@ impl State {
                             mem_usage as u32,
                         ))
                     })
-                    .unwrap_or_else(|| Ok((None, ProcessStatus::Stopped, 0u32, 0u32)))?;
+                    .unwrap_or(Ok((None, handle.status(), 0u32, 0u32)))?;
 
                 Ok::<ListResponse, Error>(ListResponse {
                     pid,
                     status,
+                    restarts: handle.restarts,
+                    exit: handle.last_exit().cloned(),
+                    health: handle.health().map(|health| health.status),
+                    job: handle.runs(),
                     cpu_usage,
                     mem_usage,
                     name: name.clone(),
@@ -145,7 +769,16 @@ impl State {
     }
 
     pub async fn start(self: Arc<Self>, mut spec: ProcessSpec) -> Result<ProcessInfo, Error> {
+        validate_spec(&spec)?;
+
+        //? walking the watched directories may take a while, so it is done before taking the lock.
+        let watcher = match spec.watch.as_ref() {
+            Some(watch) => Some(Watcher::build(watch, &spec.cwd).await?),
+            None => None,
+        };
+
         let mut locked = self.inner.lock().await;
+        locked.ensure_running()?;
 
         if locked.processes.contains_key(spec.name.as_str()) {
             return Err(Error::from(PersistError::ProcessAlreadyExists));
@@ -190,126 +823,562 @@ impl State {
         spec.stdout_path = stdout_path.canonicalize()?;
         spec.stderr_path = stderr_path.canonicalize()?;
 
-        locked
-            .processes
-            .insert(spec.name.clone(), ProcessHandle::new(spec.clone()));
-
-        let info = match spec.status {
-            ProcessStatus::Running => {
-                let handle = locked.processes.get_mut(&spec.name).unwrap();
-
+        let channels = locked.new_channels(&spec);
+        locked.processes.insert(
+            spec.name.clone(),
+            ProcessHandle::new(spec.clone(), channels),
+        );
+
+        self.changed();
+
+        let handle = locked.processes.get_mut(&spec.name).unwrap();
+        self.clone().watch_files(handle, watcher);
+        if let ProcessStatus::Running | ProcessStatus::Scheduled = spec.status {
+            //? cron jobs only run once their schedule comes up.
+            if schedule::is_scheduled(spec.job.as_ref()) {
+                self.clone().schedule(handle);
+            } else {
                 let future = handle.start().await?;
-
-                let name = handle.name().to_string();
                 let pid = handle.pid().unwrap();
-                let cloned_self = self.clone();
-                tokio::spawn(async move {
-                    let _ = future.await;
-                    let mut locked = cloned_self.inner.lock().await;
-                    if let Some(handle) = locked.processes.get_mut(name.as_str()) {
-                        if matches!(handle.pid(), Some(inner_pid) if pid == inner_pid) {
-                            let _ = handle.process.take();
-                            // TODO: restart process ?
-                        }
-                    }
-                });
-
-                ProcessInfo {
-                    name: spec.name,
-                    cmd: spec.cmd,
-                    cwd: spec.cwd,
-                    env: spec.env,
-                    pid: Some(pid),
-                    status: spec.status,
-                    created_at: spec.created_at,
-                    pid_path: spec.pid_path,
-                    stdout_path: spec.stdout_path,
-                    stderr_path: spec.stderr_path,
-                }
+                self.clone().watch(spec.name.clone(), pid, future);
             }
-            ProcessStatus::Stopped => ProcessInfo {
-                name: spec.name,
-                cmd: spec.cmd,
-                cwd: spec.cwd,
-                env: spec.env,
-                pid: None,
-                status: spec.status,
-                created_at: spec.created_at,
-                pid_path: spec.pid_path,
-                stdout_path: spec.stdout_path,
-                stderr_path: spec.stderr_path,
-            },
-        };
+        }
 
-        Ok(info)
+        Ok(handle.info())
     }
 
-    pub async fn stop(&self, name: impl AsRef<str>) -> Result<(), Error> {
-        let mut locked = self.inner.lock().await;
+    /// Stops a process, optionally overriding the signal and timeout from its specification.
+    pub async fn stop(
+        &self,
+        name: impl AsRef<str>,
+        signal: Option<Signal>,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let stopping = {
+            let mut locked = self.inner.lock().await;
+
+            let handle = locked
+                .processes
+                .get_mut(name.as_ref())
+                .ok_or(PersistError::ProcessNotFound)?;
 
-        let handle = locked
-            .processes
-            .get_mut(name.as_ref())
-            .ok_or(PersistError::ProcessNotFound)?;
+            handle.unschedule();
+            handle.begin_stop(signal, timeout)?
+        };
 
-        handle.stop().await?;
+        //? wait for the process to exit without holding the lock, to keep serving other requests meanwhile.
+        if let Some(stopping) = stopping {
+            let exit = stopping.wait().await?;
+
+            let mut locked = self.inner.lock().await;
+            if let Some(handle) = locked.processes.get_mut(name.as_ref()) {
+                //? don't overwrite anything if the process got started again in the meantime.
+                if handle.process.is_none() {
+                    handle.stopped(exit);
+                }
+            }
+        }
+
+        self.changed();
 
         Ok(())
     }
 
     pub async fn restart(self: Arc<Self>, spec: ProcessSpec) -> Result<ProcessInfo, Error> {
-        let mut locked = self.inner.lock().await;
+        let mut watcher = None;
+        let stopping = loop {
+            let mut locked = self.inner.lock().await;
+            locked.ensure_running()?;
+
+            let handle = locked
+                .processes
+                .get_mut(spec.name.as_str())
+                .ok_or(PersistError::ProcessNotFound)?;
+
+            //? the files are watched again only if what gets watched changed, so that restarts triggered
+            //? by the watcher (which are made with the same specification) do not interrupt it.
+            let rewatch = handle.spec().watch != spec.watch || handle.spec().cwd != spec.cwd;
+
+            //? walking the watched directories may take a while, so it is not done while holding the lock.
+            if let (true, Some(watch), None) = (rewatch, spec.watch.as_ref(), watcher.as_ref()) {
+                drop(locked);
+                watcher = Some(Watcher::build(watch, &spec.cwd).await?);
+                continue;
+            }
 
+            handle.spec = spec.clone();
+            if rewatch {
+                self.clone().watch_files(handle, watcher.take());
+            }
+            self.changed();
+            handle.restarts = 0;
+
+            //? restarting a cron job runs it right away, and then on its (possibly new) schedule.
+            match schedule::is_scheduled(spec.job.as_ref()) {
+                true => self.clone().schedule(handle),
+                false => handle.unschedule(),
+            }
+
+            break handle.begin_stop(None, None)?;
+        };
+
+        self.respawn(spec.name, stopping).await
+    }
+
+    /// Starts a process again once its previous run (if any) exited, reporting it as restarted.
+    ///
+    /// The lock is not held while waiting for the previous run to exit,
+    /// so that a process slow to stop does not prevent other requests from being served meanwhile.
+    async fn respawn(
+        self: Arc<Self>,
+        name: String,
+        stopping: Option<Stopping>,
+    ) -> Result<ProcessInfo, Error> {
+        let exit = match stopping {
+            Some(stopping) => Some(stopping.wait().await?),
+            None => None,
+        };
+
+        let mut locked = self.inner.lock().await;
+        locked.ensure_running()?;
         let handle = locked
             .processes
-            .get_mut(spec.name.as_str())
+            .get_mut(name.as_str())
             .ok_or(PersistError::ProcessNotFound)?;
 
-        let future = handle.restart_with_spec(spec.clone()).await?;
+        //? don't start it twice if the process got started again in the meantime.
+        if handle.process.is_some() {
+            return Ok(handle.info());
+        }
+        if let Some(exit) = exit {
+            handle.stopped(exit);
+        }
+
+        locked.refresh_channels(name.as_str());
+        let handle = locked.processes.get_mut(name.as_str()).unwrap();
+
+        let future = handle.respawn().await;
+        self.changed();
+        let future = future?;
 
-        let name = handle.name().to_string();
         let pid = handle.pid().unwrap();
-        let cloned_self = self.clone();
+        self.clone().watch(name, pid, future);
+
+        Ok(handle.info())
+    }
+
+    /// Watches for the termination of a process and applies its restart policy if it exited on its own.
+    ///
+    /// This also starts monitoring the health of this run of the process.
+    fn watch(self: Arc<Self>, name: String, pid: usize, future: ExitFuture) {
+        self.clone().monitor(name.clone(), pid);
         tokio::spawn(async move {
-            let _ = future.await;
-            let mut locked = cloned_self.inner.lock().await;
-            if let Some(handle) = locked.processes.get_mut(name.as_str()) {
-                if matches!(handle.pid(), Some(inner_pid) if pid == inner_pid) {
-                    let _ = handle.process.take();
-                    // TODO: restart process ?
+            let status = future.await;
+            let mut locked = self.inner.lock().await;
+            let shutting_down = locked.shutting_down;
+            let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                return;
+            };
+
+            //? the process got stopped or restarted in the meantime, so it did not exit on its own.
+            if handle.pid() != Some(pid) {
+                return;
+            }
+
+            let delay = handle.exited(status);
+            self.changed();
+            if shutting_down {
+                return;
+            }
+
+            //? a run of the job was waiting for this one to exit, so it takes the place of any restart.
+            if handle.take_queued() {
+                match handle.launch().await {
+                    Ok(future) => {
+                        let pid = handle.pid().unwrap();
+                        self.clone().watch(name, pid, future);
+                    }
+                    Err(err) => {
+                        eprintln!("could not run queued '{}': {}", name, err);
+                    }
+                }
+                return;
+            }
+
+            let Some(delay) = delay else {
+                return;
+            };
+
+            let cloned_self = self.clone();
+            let task = tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let mut locked = cloned_self.inner.lock().await;
+                let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                    return;
+                };
+
+                //? detach this task from the handle, so that starting the process does not abort it.
+                let _ = handle.pending_restart.take();
+                if handle.process.is_some() {
+                    return;
+                }
+
+                match handle.start().await {
+                    Ok(future) => {
+                        let pid = handle.pid().unwrap();
+                        cloned_self.changed();
+                        cloned_self.clone().watch(name, pid, future);
+                    }
+                    Err(err) => {
+                        eprintln!("could not restart '{}': {}", name, err);
+                    }
                 }
+            });
+            handle.pending_restart = Some(task);
+        });
+    }
+
+    /// Runs a job every time its cron schedule comes up, until it gets unscheduled (replacing any previous schedule).
+    ///
+    /// Runs that come up while the previous one is still going are handled according to the job's overlap policy.
+    fn schedule(self: Arc<Self>, handle: &mut ProcessHandle) {
+        handle.unschedule();
+
+        let Some(job) = handle.spec().job.clone() else {
+            return;
+        };
+        let Some(first) = schedule::next_run(&job, chrono::Local::now()) else {
+            return;
+        };
+        handle.job.next_run = Some(first.naive_local());
+
+        let name = handle.name().to_string();
+        let task = tokio::spawn(async move {
+            let mut next = first;
+            loop {
+                tokio::time::sleep(schedule::until(next)).await;
+
+                let mut locked = self.inner.lock().await;
+                if locked.shutting_down {
+                    return;
+                }
+                let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                    return;
+                };
+
+                let started = match handle.run().await {
+                    Ok(Run::Started(future)) => Ok(Some(future)),
+                    Ok(Run::Deferred) => Ok(None),
+                    Ok(Run::Replacing(stopping)) => {
+                        //? wait for the previous run to exit without holding the lock, to keep serving other requests meanwhile.
+                        drop(locked);
+                        let exit = stopping.wait().await;
+
+                        locked = self.inner.lock().await;
+                        if locked.shutting_down {
+                            return;
+                        }
+                        let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                            return;
+                        };
+
+                        match exit {
+                            //? the process may have been started again in the meantime, which this run gives way to.
+                            Ok(_) if handle.process.is_some() => Ok(None),
+                            Ok(exit) => {
+                                handle.stopped(exit);
+                                self.changed();
+                                handle.launch().await.map(Some)
+                            }
+                            Err(err) => Err(err),
+                        }
+                    }
+                    Err(err) => Err(err),
+                };
+
+                let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                    return;
+                };
+                match started {
+                    Ok(Some(future)) => {
+                        let pid = handle.pid().unwrap();
+                        self.changed();
+                        self.clone().watch(name.clone(), pid, future);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        eprintln!("could not run '{}': {}", name, err);
+                    }
+                }
+
+                //? the next run is looked up from the one that just came up, so that none of them runs twice.
+                let after = next.max(chrono::Local::now());
+                let Some(upcoming) = schedule::next_run(&job, after) else {
+                    handle.job.next_run = None;
+                    return;
+                };
+                handle.job.next_run = Some(upcoming.naive_local());
+                next = upcoming;
             }
         });
+        handle.schedule = Some(task);
+    }
+
+    /// Restarts a process every time some of its watched files change (replacing any previous watcher).
+    ///
+    /// Processes stopped on request (or waiting for their schedule) are left as they are.
+    fn watch_files(self: Arc<Self>, handle: &mut ProcessHandle, watcher: Option<Watcher>) {
+        handle.unwatch();
 
-        let info = ProcessInfo {
-            name: spec.name.clone(),
-            cmd: spec.cmd.clone(),
-            cwd: spec.cwd.clone(),
-            env: spec.env.clone(),
-            pid: Some(pid),
-            status: ProcessStatus::Running,
-            created_at: spec.created_at,
-            pid_path: spec.pid_path.clone(),
-            stdout_path: spec.stdout_path.clone(),
-            stderr_path: spec.stderr_path.clone(),
+        let (Some(mut watcher), Some(spec)) = (watcher, handle.spec().watch.as_ref()) else {
+            return;
         };
+        let debounce = Duration::from_millis(spec.debounce);
+
+        let name = handle.name().to_string();
+        let task = tokio::spawn(async move {
+            loop {
+                let paths = match watcher.changes(debounce).await {
+                    Ok(paths) => paths,
+                    Err(err) => {
+                        eprintln!("could not watch the files of '{}': {}", name, err);
+                        return;
+                    }
+                };
+
+                let spec = {
+                    let mut locked = self.inner.lock().await;
+                    if locked.shutting_down {
+                        return;
+                    }
+                    let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                        return;
+                    };
+                    if let ProcessStatus::Stopped | ProcessStatus::Scheduled = handle.status() {
+                        continue;
+                    }
+
+                    handle.files_changed(paths);
+                    handle.spec().clone()
+                };
+
+                if let Err(err) = self.clone().restart(spec).await {
+                    eprintln!("could not restart '{}' after files changed: {}", name, err);
+                }
+            }
+        });
+        handle.watcher = Some(task);
+    }
+
+    /// Periodically checks the health of a process (if it has a health check), for as long as this run of it lasts.
+    ///
+    /// Restarts the process once it becomes unhealthy, if its health check asks for it.
+    fn monitor(self: Arc<Self>, name: String, pid: usize) {
+        tokio::spawn(async move {
+            loop {
+                let (check, cwd, env) = {
+                    let locked = self.inner.lock().await;
+                    let Some(handle) = locked.processes.get(name.as_str()) else {
+                        return;
+                    };
+                    if handle.pid() != Some(pid) {
+                        return;
+                    }
+                    let spec = handle.spec();
+                    let Some(check) = spec.health_check.clone() else {
+                        return;
+                    };
+                    (check, spec.cwd.clone(), spec.env.clone())
+                };
+
+                tokio::time::sleep(Duration::from_millis(check.interval)).await;
+                let result = health::check(&check, &cwd, &env).await;
+
+                let mut locked = self.inner.lock().await;
+                if locked.shutting_down {
+                    return;
+                }
+                let Some(handle) = locked.processes.get_mut(name.as_str()) else {
+                    return;
+                };
+
+                //? the process got stopped or restarted while it was being checked.
+                if handle.pid() != Some(pid) {
+                    return;
+                }
 
-        Ok(info)
+                if !handle.checked(result) {
+                    continue;
+                }
+
+                let stopping = handle.begin_stop(None, None);
+                drop(locked);
+
+                let outcome = match stopping {
+                    Ok(stopping) => self.clone().respawn(name.clone(), stopping).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = outcome {
+                    eprintln!("could not restart unhealthy '{}': {}", name, err);
+                }
+                return;
+            }
+        });
     }
 
     pub async fn delete(&self, name: impl AsRef<str>) -> Result<(), Error> {
-        let mut locked = self.inner.lock().await;
+        let mut handle = {
+            let mut locked = self.inner.lock().await;
 
-        let mut handle = locked
-            .processes
-            .remove(name.as_ref())
-            .ok_or(PersistError::ProcessNotFound)?;
+            let handle = locked
+                .processes
+                .remove(name.as_ref())
+                .ok_or(PersistError::ProcessNotFound)?;
+            locked.prune_channels();
+            handle
+        };
+        self.changed();
 
+        handle.unschedule();
+        handle.unwatch();
         handle.stop().await?;
+        handle
+            .channels
+            .send_event(handle.name(), LifecycleEvent::Deleted);
 
         Ok(())
     }
 
+    /// Starts the given replicas of a process.
+    ///
+    /// No partial group is left behind if one of them fails to start: the replicas started so far get deleted,
+    /// along with the failed one if it got registered (but not a process that already had its name).
+    pub async fn start_replicas(
+        self: Arc<Self>,
+        spec: &ProcessSpec,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Result<Vec<ProcessInfo>, Error> {
+        let mut started = Vec::new();
+        for index in indices {
+            let replica = replicas::replica(spec, index);
+            let name = replica.name.clone();
+            let err = match self.clone().start(replica).await {
+                Ok(info) => {
+                    started.push(info);
+                    continue;
+                }
+                Err(err) => err,
+            };
+
+            let registered = self
+                .with_handle(name.as_str(), |handle| {
+                    handle.spec().group() == spec.group()
+                })
+                .await
+                .unwrap_or(false);
+            let failed = registered.then_some(name);
+            let names = started.iter().map(|info| info.name.clone()).chain(failed);
+            for name in names.rev() {
+                if let Err(err) = self.delete(name.as_str()).await {
+                    eprintln!("could not delete '{}': {}", name, err);
+                }
+            }
+
+            return Err(err);
+        }
+
+        Ok(started)
+    }
+
+    /// Changes the number of replicas of a process, starting the missing ones and deleting the extra ones.
+    ///
+    /// The new replicas are started only if the existing ones are running.
+    pub async fn scale(
+        self: Arc<Self>,
+        group: String,
+        count: usize,
+    ) -> Result<ScaleResponse, Error> {
+        if count == 0 {
+            return Err(Error::from(String::from(
+                "the number of instances must be at least 1",
+            )));
+        }
+
+        let (template, members, running) = {
+            let mut locked = self.inner.lock().await;
+            locked.ensure_running()?;
+            let members = locked.members(group.as_str());
+            let Some(first) = members.first() else {
+                return match locked.processes.contains_key(group.as_str()) {
+                    true => Err(Error::from(format!(
+                        "process '{}' does not run as multiple instances",
+                        group
+                    ))),
+                    false => Err(Error::from(PersistError::ProcessNotFound)),
+                };
+            };
+
+            let first = &locked.processes[first.as_str()];
+            let template = first.spec().clone();
+            let running = matches!(
+                first.status(),
+                ProcessStatus::Running | ProcessStatus::Scheduled
+            );
+
+            //? the replicas that are kept remember the new count, for it to get persisted and dumped.
+            locked.set_instances(&members, count);
+
+            (template, members, running)
+        };
+
+        let mut deleted = Vec::new();
+        for name in members.iter().rev() {
+            let index = replicas::index_of(group.as_str(), name.as_str());
+            if index.is_some_and(|index| index >= count) {
+                self.delete(name).await?;
+                deleted.push(name.clone());
+            }
+        }
+        deleted.reverse();
+
+        let missing = (0..count).filter(|index| {
+            let name = replicas::name(group.as_str(), *index);
+            !members.contains(&name)
+        });
+
+        let mut spec = template.clone();
+        if let Some(instances) = spec.instances.as_mut() {
+            instances.count = count;
+        }
+        spec.status = match running {
+            true => ProcessStatus::Running,
+            false => ProcessStatus::Stopped,
+        };
+
+        let created = match self.clone().start_replicas(&spec, missing).await {
+            Ok(started) => started.into_iter().map(|info| info.name).collect(),
+            Err(err) => {
+                //? the new replicas got deleted, so the kept ones go back to the previous count.
+                if let Some(instances) = template.instances.as_ref() {
+                    self.inner
+                        .lock()
+                        .await
+                        .set_instances(&members, instances.count);
+                    self.changed();
+                }
+                return Err(err);
+            }
+        };
+
+        self.changed();
+
+        Ok(ScaleResponse {
+            name: group,
+            created,
+            deleted,
+        })
+    }
+
     pub async fn dump(&self, filters: Option<Vec<String>>) -> Result<Vec<ProcessSpec>, Error> {
         let locked = self.inner.lock().await;
 
@@ -326,8 +1395,8 @@ impl State {
                 .collect(),
             None => locked
                 .processes
-                .iter()
-                .map(|(_, handle)| {
+                .values()
+                .map(|handle| {
                     let mut spec = handle.spec().clone();
                     spec.status = handle.status();
                     spec
@@ -340,107 +1409,108 @@ impl State {
 
     pub async fn logs(
         &self,
-        filters: Option<Vec<String>>,
-        lines: usize,
-        stream: bool,
-        source_filter: Option<LogStreamSource>,
-    ) -> Result<impl Stream<Item = LogEntry>, Error> {
-        let locked = self.inner.lock().await;
+        request: LogsRequest,
+    ) -> Result<impl Stream<Item = LogsResponse>, Error> {
+        let LogsRequest {
+            filters,
+            source_filter,
+            stream,
+            lines,
+            since,
+            until,
+            resume_on_lag,
+            search,
+            level,
+            fields,
+        } = request;
+        let filter = EntryFilter {
+            since,
+            until,
+            level,
+            fields,
+        };
+        let search = search.as_ref().map(Search::new).transpose()?;
+
+        //? what is needed from the processes is gathered under the lock, which is released before reading their files.
+        let (sources, followed) = {
+            let mut locked = self.inner.lock().await;
+            locked.prune_channels();
 
-        let streams = future::try_join_all(
-            locked
+            let mut sources = Vec::new();
+            let handles = locked
                 .processes
                 .iter()
-                .filter(|(name, _)| filters.as_ref().map_or(true, |names| names.contains(name)))
-                .map(|(_, handle)| async move {
-                    let stdout_init = match (source_filter, lines) {
-                        (Some(LogStreamSource::Stderr), _) | (_, 0) => {
-                            futures::stream::empty().right_stream()
-                        }
-                        (_, lines) => {
-                            let contents = tokio::fs::read_to_string(handle.stdout_file()).await?;
-                            let lines = contents
-                                .split('\n')
-                                .rev()
-                                .skip(1)
-                                .take(lines)
-                                .map(String::from)
-                                .collect::<Vec<_>>();
-                            futures::stream::iter(lines.into_iter().rev()).left_stream()
-                        }
+                .filter(|(name, _)| filters.as_ref().is_none_or(|names| names.contains(name)))
+                .map(|(_, handle)| handle);
+            for handle in handles {
+                let streams = [
+                    (LogStreamSource::Stdout, handle.stdout_file()),
+                    (LogStreamSource::Stderr, handle.stderr_file()),
+                ];
+                for (source, path) in streams {
+                    if source_filter.is_some_and(|filter| filter != source) {
+                        continue;
+                    }
+                    let history = match search {
+                        Some(_) => handle.log_history(source),
+                        None => Vec::new(),
                     };
+                    let origin = (handle.name().to_string(), source, handle.spec().log_format);
+                    sources.push((origin, path.to_path_buf(), history));
+                }
+            }
 
-                    let stderr_init = match (source_filter, lines) {
-                        (Some(LogStreamSource::Stdout), _) | (_, 0) => {
-                            futures::stream::empty().right_stream()
+            let followed = match stream {
+                true => Some(locked.follow(
+                    filters,
+                    source_filter,
+                    search.as_ref(),
+                    &filter,
+                    resume_on_lag,
+                )?),
+                false => None,
+            };
+
+            (sources, followed)
+        };
+
+        //? the backlog of every stream is merged into a single timeline, from which only the last lines are kept.
+        //? when searching, these are the last matches (along with their context), looked up in rotated files as well.
+        let backlogs = future::try_join_all(sources.into_iter().map(
+            |((name, source, format), path, history)| {
+                let search = search.as_ref();
+                let filter = &filter;
+                async move {
+                    let origin = (name.as_str(), source, format);
+                    match search {
+                        Some(search) => {
+                            search::search_files(search, history, origin, lines, filter).await
                         }
-                        (_, lines) => {
-                            let contents = tokio::fs::read_to_string(handle.stderr_file()).await?;
-                            let lines = contents
-                                .split('\n')
-                                .rev()
-                                .skip(1)
-                                .take(lines)
-                                .map(String::from)
-                                .collect::<Vec<_>>();
-                            futures::stream::iter(lines.into_iter().rev()).left_stream()
+                        None => {
+                            let entries = read_entries(&path, origin, lines, filter).await?;
+                            Ok(entries.into_iter().map(|entry| vec![entry]).collect())
                         }
-                    };
-
-                    if stream {
-                        let name = handle.name().to_string();
-                        let stdout = match source_filter {
-                            Some(LogStreamSource::Stderr) => {
-                                futures::stream::empty().right_stream()
-                            }
-                            _ => handle.stdout().left_stream(),
-                        };
-                        let stdout = stdout_init.chain(stdout).map(move |msg| LogEntry {
-                            msg,
-                            name: name.clone(),
-                            source: LogStreamSource::Stdout,
-                        });
-
-                        let name = handle.name().to_string();
-                        let stderr = match source_filter {
-                            Some(LogStreamSource::Stdout) => {
-                                futures::stream::empty().right_stream()
-                            }
-                            _ => handle.stderr().left_stream(),
-                        };
-                        let stderr = stderr_init.chain(stderr).map(move |msg| LogEntry {
-                            msg,
-                            name: name.clone(),
-                            source: LogStreamSource::Stderr,
-                        });
-
-                        Ok::<_, Error>(Box::pin(
-                            futures::stream::select(stdout, stderr).right_stream(),
-                        ))
-                    } else {
-                        let name = handle.name().to_string();
-                        let stdout = stdout_init.map(move |msg| LogEntry {
-                            msg,
-                            name: name.clone(),
-                            source: LogStreamSource::Stdout,
-                        });
-
-                        let name = handle.name().to_string();
-                        let stderr = stderr_init.map(move |msg| LogEntry {
-                            msg,
-                            name: name.clone(),
-                            source: LogStreamSource::Stderr,
-                        });
-
-                        Ok::<_, Error>(Box::pin(
-                            futures::stream::select(stdout, stderr).left_stream(),
-                        ))
                     }
-                }),
-        )
+                }
+            },
+        ))
         .await?;
+        let backlog = merge_backlogs(backlogs, lines);
+        let backlog = futures::stream::iter(backlog.into_iter().map(LogsResponse::Entry));
+
+        let Some(followed) = followed else {
+            return Ok(backlog.left_stream());
+        };
+
+        let live = futures::stream::select_all(followed).filter(move |item| {
+            let keep = match item {
+                LogsResponse::Event(event) => until.is_none_or(|until| event.timestamp <= until),
+                _ => true,
+            };
+            future::ready(keep)
+        });
 
-        Ok(futures::stream::select_all(streams))
+        Ok(backlog.chain(live).right_stream())
     }
 
     pub async fn prune(&self, stopped: bool) -> Result<Vec<String>, Error> {
@@ -450,12 +1520,18 @@ impl State {
         let expected_files: Vec<PathBuf> = locked
             .processes
             .values()
-            .filter(|handle| !stopped || (stopped && handle.status() == ProcessStatus::Running))
+            .filter(|handle| {
+                !stopped
+                    || matches!(
+                        handle.status(),
+                        ProcessStatus::Running | ProcessStatus::Scheduled
+                    )
+            })
             .flat_map(|handle| {
-                let fst = std::iter::once(PathBuf::from(handle.pid_file()));
-                let snd = std::iter::once(PathBuf::from(handle.stdout_file()));
-                let trd = std::iter::once(PathBuf::from(handle.stderr_file()));
-                fst.chain(snd).chain(trd)
+                let pid_file = std::iter::once(PathBuf::from(handle.pid_file()));
+                pid_file
+                    .chain(handle.log_files())
+                    .chain(handle.pipe_files())
             })
             .collect();
 
@@ -466,34 +1542,30 @@ impl State {
         // upon the first process start-up, so it can legitimately not be there yet.
         if let Ok(mut logs) = logs {
             while let Some(dirent) = logs.next_entry().await? {
-                // Ignore non-regular files (like directories).
+                // Ignore anything but regular files and named pipes (like directories).
                 let kind = dirent.file_type().await?;
-                if !kind.is_file() {
+                if !kind.is_file() && !kind.is_fifo() {
                     continue;
                 }
 
                 let path = dirent.path().canonicalize()?;
-                if !expected_files.contains(&path) {
-                    if let Ok(_) = tokio::fs::remove_file(&path).await {
-                        pruned_files.push(path.display().to_string());
-                    }
+                if !expected_files.contains(&path) && tokio::fs::remove_file(&path).await.is_ok() {
+                    pruned_files.push(path.display().to_string());
                 }
             }
         }
 
         if let Ok(mut pids) = pids {
             while let Some(dirent) = pids.next_entry().await? {
-                // Ignore non-regular files (like directories).
+                // Ignore anything but regular files and named pipes (like directories).
                 let kind = dirent.file_type().await?;
-                if !kind.is_file() {
+                if !kind.is_file() && !kind.is_fifo() {
                     continue;
                 }
 
                 let path = dirent.path().canonicalize()?;
-                if !expected_files.contains(&path) {
-                    if let Ok(_) = tokio::fs::remove_file(&path).await {
-                        pruned_files.push(path.display().to_string());
-                    }
+                if !expected_files.contains(&path) && tokio::fs::remove_file(&path).await.is_ok() {
+                    pruned_files.push(path.display().to_string());
                 }
             }
         }
@@ -501,3 +1573,73 @@ impl State {
         Ok(pruned_files)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+
+    fn entry(name: &str, source: LogStreamSource, second: u32, context: bool) -> LogEntry {
+        LogEntry {
+            source,
+            context,
+            name: name.to_string(),
+            timestamp: Local.with_ymd_and_hms(2024, 1, 1, 0, 0, second).single(),
+            msg: format!("{} at {}", name, second),
+            structured: None,
+        }
+    }
+
+    fn singles(entries: Vec<LogEntry>) -> Vec<Vec<LogEntry>> {
+        entries.into_iter().map(|entry| vec![entry]).collect()
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.msg.as_str()).collect()
+    }
+
+    #[test]
+    fn backlogs_are_merged_chronologically() {
+        let (out, err) = (LogStreamSource::Stdout, LogStreamSource::Stderr);
+        let backlogs = vec![
+            singles(vec![
+                entry("web", out, 1, false),
+                entry("web", out, 4, false),
+            ]),
+            singles(vec![
+                entry("web", err, 2, false),
+                entry("web", err, 6, false),
+            ]),
+            singles(vec![entry("db", out, 3, false), entry("db", out, 5, false)]),
+        ];
+
+        let merged = merge_backlogs(backlogs.clone(), 10);
+        let expected = [
+            "web at 1", "web at 2", "db at 3", "web at 4", "db at 5", "web at 6",
+        ];
+        assert_eq!(messages(&merged), expected);
+
+        //? only the last lines of the merged timeline are kept, whatever stream they come from.
+        let merged = merge_backlogs(backlogs, 3);
+        assert_eq!(messages(&merged), ["web at 4", "db at 5", "web at 6"]);
+    }
+
+    #[test]
+    fn matches_are_kept_along_with_their_context() {
+        let out = LogStreamSource::Stdout;
+        let backlogs = vec![
+            vec![
+                vec![entry("web", out, 1, true), entry("web", out, 2, false)],
+                vec![entry("web", out, 5, false), entry("web", out, 6, true)],
+            ],
+            vec![vec![entry("db", out, 3, false), entry("db", out, 4, true)]],
+        ];
+
+        let merged = merge_backlogs(backlogs, 2);
+        assert_eq!(
+            messages(&merged),
+            ["db at 3", "db at 4", "web at 5", "web at 6"]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use persist_core::protocol::{LogFormat, LogLevel, StructuredLog};
+
+/// The fields from which the level of an entry gets extracted (the first one present wins).
+const LEVEL_FIELDS: &[&str] = &["level", "lvl", "severity", "loglevel"];
+/// The fields from which the message of an entry gets extracted (the first one present wins).
+const MESSAGE_FIELDS: &[&str] = &["msg", "message"];
+/// The fields from which the timestamp of an entry gets extracted (the first one present wins).
+const TIME_FIELDS: &[&str] = &["time", "timestamp", "ts", "@timestamp"];
+
+/// Removes the first of the given fields that is present and that can be converted, returning its converted value.
+fn extract<T>(
+    fields: &mut BTreeMap<String, json::Value>,
+    keys: &[&str],
+    convert: impl Fn(&json::Value) -> Option<T>,
+) -> Option<T> {
+    let key = keys.iter().find(|key| fields.contains_key(**key))?;
+    let value = convert(fields.get(*key)?)?;
+    fields.remove(*key);
+    Some(value)
+}
+
+/// Parses a log message according to the process' log format.
+///
+/// Returns `None` for plain text lines, or if the message is not a JSON object.
+pub fn parse(format: LogFormat, msg: &str) -> Option<StructuredLog> {
+    if let LogFormat::Text = format {
+        return None;
+    }
+
+    let json::Value::Object(fields) = json::from_str(msg).ok()? else {
+        return None;
+    };
+    let mut fields: BTreeMap<String, json::Value> = fields.into_iter().collect();
+
+    let level = extract(&mut fields, LEVEL_FIELDS, |value| match value {
+        json::Value::String(level) => level.parse().ok(),
+        json::Value::Number(level) => level.as_u64().map(LogLevel::from_number),
+        _ => None,
+    });
+    let message = extract(&mut fields, MESSAGE_FIELDS, |value| {
+        value.as_str().map(String::from)
+    });
+    let time = extract(&mut fields, TIME_FIELDS, |value| match value {
+        json::Value::String(time) => Some(time.clone()),
+        json::Value::Number(time) => Some(time.to_string()),
+        _ => None,
+    });
+
+    Some(StructuredLog {
+        level,
+        message,
+        time,
+        fields,
+    })
+}
+
+/// Gets the value of a field of a structured entry, as a string.
+///
+/// Nested fields are separated by dots (as in `http.status`).
+/// The extracted fields can also be referred to, by the first of their usual names.
+pub fn field(log: &StructuredLog, key: &str) -> Option<String> {
+    let mut path = key.split('.');
+    let mut value = log.fields.get(path.next()?);
+    for key in path {
+        value = match value? {
+            json::Value::Object(fields) => fields.get(key),
+            json::Value::Array(items) => key.parse().ok().and_then(|index: usize| items.get(index)),
+            _ => None,
+        };
+    }
+
+    match value {
+        Some(json::Value::String(value)) => Some(value.clone()),
+        Some(value) => Some(value.to_string()),
+        None if key == LEVEL_FIELDS[0] => log.level.map(|level| level.to_string()),
+        None if key == MESSAGE_FIELDS[0] => log.message.clone(),
+        None if key == TIME_FIELDS[0] => log.time.clone(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_json(msg: &str) -> StructuredLog {
+        parse(LogFormat::Json, msg).unwrap()
+    }
+
+    #[test]
+    fn text_lines_are_not_parsed() {
+        assert_eq!(parse(LogFormat::Text, r#"{"level":"info"}"#), None);
+        assert_eq!(parse(LogFormat::Json, "plain line"), None);
+        assert_eq!(parse(LogFormat::Json, "[1, 2]"), None);
+    }
+
+    #[test]
+    fn level_names() {
+        let level = |msg: &str| parse_json(msg).level;
+        assert_eq!(level(r#"{"level":"info"}"#), Some(LogLevel::Info));
+        assert_eq!(level(r#"{"lvl":"WARNING"}"#), Some(LogLevel::Warn));
+        assert_eq!(level(r#"{"severity":"err"}"#), Some(LogLevel::Error));
+        assert_eq!(level(r#"{"loglevel":"critical"}"#), Some(LogLevel::Fatal));
+        assert_eq!(level(r#"{"level":"verbose"}"#), None);
+        assert_eq!(level(r#"{"msg":"no level"}"#), None);
+    }
+
+    #[test]
+    fn numeric_levels() {
+        //? as output by `pino` or `bunyan`.
+        let level = |msg: &str| parse_json(msg).level;
+        assert_eq!(level(r#"{"level":10}"#), Some(LogLevel::Trace));
+        assert_eq!(level(r#"{"level":30}"#), Some(LogLevel::Info));
+        assert_eq!(level(r#"{"level":50}"#), Some(LogLevel::Error));
+        assert_eq!(level(r#"{"level":60}"#), Some(LogLevel::Fatal));
+        assert_eq!(level(r#"{"level":-1}"#), None);
+    }
+
+    #[test]
+    fn extracted_fields_are_removed() {
+        let log = parse_json(r#"{"level":"info","message":"started","ts":1700000000,"port":8080}"#);
+        assert_eq!(log.level, Some(LogLevel::Info));
+        assert_eq!(log.message.as_deref(), Some("started"));
+        assert_eq!(log.time.as_deref(), Some("1700000000"));
+        assert_eq!(log.fields.keys().collect::<Vec<_>>(), ["port"]);
+    }
+
+    #[test]
+    fn unrecognized_values_are_kept_as_fields() {
+        let log = parse_json(r#"{"level":"verbose","msg":{"nested":true}}"#);
+        assert_eq!(log.level, None);
+        assert_eq!(log.message, None);
+        assert_eq!(log.fields.keys().collect::<Vec<_>>(), ["level", "msg"]);
+    }
+
+    #[test]
+    fn nested_fields() {
+        let log = parse_json(r#"{"http":{"status":404,"path":"/"},"tags":["a","b"]}"#);
+        assert_eq!(field(&log, "http.status").as_deref(), Some("404"));
+        assert_eq!(field(&log, "http.path").as_deref(), Some("/"));
+        assert_eq!(field(&log, "tags.1").as_deref(), Some("b"));
+        assert_eq!(field(&log, "http.missing"), None);
+        assert_eq!(field(&log, "tags.2"), None);
+    }
+
+    #[test]
+    fn extracted_fields_by_their_usual_names() {
+        let log = parse_json(r#"{"severity":"debug","message":"hello","@timestamp":"now"}"#);
+        assert_eq!(field(&log, "level").as_deref(), Some("debug"));
+        assert_eq!(field(&log, "msg").as_deref(), Some("hello"));
+        assert_eq!(field(&log, "time").as_deref(), Some("now"));
+    }
+}
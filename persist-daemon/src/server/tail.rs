@@ -0,0 +1,220 @@
+use std::io::SeekFrom;
+use std::path::Path;
+
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use persist_core::error::Error;
+
+/// The size of the chunks read from the end of the file.
+const CHUNK_SIZE: u64 = 8 * 1024;
+
+/// A reader yielding the lines of a file backwards, starting from its end.
+///
+/// The file is read by chunks, so memory usage is bounded by the length of the longest line
+/// (instead of the size of the whole file).
+/// A trailing line not (yet) terminated by a newline is skipped, since it may still be getting written.
+pub struct ReverseLines {
+    file: File,
+    /// The offset in the file up to which the contents are already read.
+    position: u64,
+    /// The contents read from the file that have not been yielded yet.
+    buffer: Vec<u8>,
+    /// Whether the trailing (unterminated) line still needs to be skipped.
+    skip_trailing: bool,
+    /// Whether the first line of the file got yielded.
+    done: bool,
+}
+
+impl ReverseLines {
+    /// Opens a file to read its lines backwards.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(path).await?;
+        let position = file.metadata().await?.len();
+
+        Ok(Self {
+            file,
+            position,
+            buffer: Vec::new(),
+            skip_trailing: true,
+            done: false,
+        })
+    }
+
+    /// Gets the previous line of the file (or `None` once its start got reached).
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    pub async fn prev_line(&mut self) -> Result<Option<String>, Error> {
+        loop {
+            if let Some(index) = self.buffer.iter().rposition(|&byte| byte == b'\n') {
+                let line = self.buffer.split_off(index + 1);
+                self.buffer.truncate(index);
+                if std::mem::take(&mut self.skip_trailing) {
+                    continue;
+                }
+                return Ok(Some(String::from_utf8_lossy(&line).into_owned()));
+            }
+
+            if self.position == 0 {
+                //? what remains is the first line of the file (unless it is the trailing one).
+                if self.done || self.skip_trailing {
+                    return Ok(None);
+                }
+                self.done = true;
+                let line = std::mem::take(&mut self.buffer);
+                return Ok(Some(String::from_utf8_lossy(&line).into_owned()));
+            }
+
+            let start = self.position.saturating_sub(CHUNK_SIZE);
+            let mut chunk = vec![0u8; (self.position - start) as usize];
+            self.file.seek(SeekFrom::Start(start)).await?;
+            self.file.read_exact(&mut chunk).await?;
+
+            chunk.extend_from_slice(&self.buffer);
+            self.buffer = chunk;
+            self.position = start;
+        }
+    }
+}
+
+/// Gets the lines of a file directly preceding the last occurrence of the given line (oldest first).
+///
+/// Returns `None` if that line cannot be found, or if it is not preceded by enough lines.
+pub async fn lines_before(
+    path: impl AsRef<Path>,
+    anchor: &str,
+    count: usize,
+) -> Result<Option<Vec<String>>, Error> {
+    let mut reader = ReverseLines::open(path).await?;
+    loop {
+        match reader.prev_line().await? {
+            Some(line) if line == anchor => break,
+            Some(_) => continue,
+            None => return Ok(None),
+        }
+    }
+
+    let mut lines = Vec::new();
+    while lines.len() < count {
+        match reader.prev_line().await? {
+            Some(line) => lines.push(line),
+            None => return Ok(None),
+        }
+    }
+    lines.reverse();
+
+    Ok(Some(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Writes a temporary file, unique to the calling test.
+    fn file(name: &str, contents: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("persist-tail-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Reads all the lines of a file backwards.
+    async fn read_back(path: &Path) -> Vec<String> {
+        let mut reader = ReverseLines::open(path).await.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.prev_line().await.unwrap() {
+            lines.push(line);
+        }
+        std::fs::remove_file(path).unwrap();
+        lines
+    }
+
+    #[tokio::test]
+    async fn lines_in_reverse() {
+        let path = file("reverse", b"first\nsecond\n\nfourth\n");
+        assert_eq!(
+            read_back(&path).await,
+            vec!["fourth", "", "second", "first"]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_file() {
+        let path = file("empty", b"");
+        assert!(read_back(&path).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_trailing_newline() {
+        //? the last line may still be getting written, so it is left out.
+        let path = file("unterminated", b"first\nsecond\npartial");
+        assert_eq!(read_back(&path).await, vec!["second", "first"]);
+
+        let path = file("unterminated-only", b"partial");
+        assert!(read_back(&path).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn lines_across_chunk_boundaries() {
+        let chunk = CHUNK_SIZE as usize;
+        let long = "x".repeat(chunk * 2 + 10);
+        let lines: Vec<String> = (0..3 * chunk / 10)
+            .map(|index| format!("line {:04}", index))
+            .chain([long.clone(), String::from("last")])
+            .collect();
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        assert!(contents.len() as u64 > 3 * CHUNK_SIZE);
+
+        let path = file("chunks", contents.as_bytes());
+        let mut expected = lines;
+        expected.reverse();
+        assert_eq!(read_back(&path).await, expected);
+    }
+
+    #[tokio::test]
+    async fn newline_right_at_a_chunk_boundary() {
+        let chunk = CHUNK_SIZE as usize;
+        let first = "a".repeat(chunk - 1);
+        let second = "b".repeat(chunk - 1);
+        let path = file("boundary", format!("{}\n{}\n", first, second).as_bytes());
+        assert_eq!(read_back(&path).await, vec![second, first]);
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8() {
+        let path = file("utf8", b"valid\nin\xffvalid\n");
+        assert_eq!(read_back(&path).await, vec!["in\u{fffd}valid", "valid"]);
+    }
+
+    #[tokio::test]
+    async fn multibyte_characters_across_chunk_boundaries() {
+        //? the chunk boundary falls in the middle of the two bytes of 'é'.
+        let padding = "a".repeat(CHUNK_SIZE as usize - 1);
+        let path = file("multibyte", format!("é{}\n", padding).as_bytes());
+        assert_eq!(read_back(&path).await, vec![format!("é{}", padding)]);
+    }
+
+    #[tokio::test]
+    async fn crlf_line_endings() {
+        //? carriage returns are kept, as they separate the lines of multi-line entries in log files.
+        let path = file("crlf", b"first\r\nsecond\r\n");
+        assert_eq!(read_back(&path).await, vec!["second\r", "first\r"]);
+    }
+
+    #[tokio::test]
+    async fn lines_before_the_last_occurrence() {
+        let path = file("before", b"a\nanchor\nb\nc\nanchor\nd\n");
+        let lines = lines_before(&path, "anchor", 2).await.unwrap();
+        assert_eq!(lines, Some(vec![String::from("b"), String::from("c")]));
+
+        let missing = lines_before(&path, "missing", 1).await.unwrap();
+        assert_eq!(missing, None);
+
+        let too_many = lines_before(&path, "anchor", 5).await.unwrap();
+        assert_eq!(too_many, None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
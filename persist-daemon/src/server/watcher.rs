@@ -0,0 +1,262 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::OsString;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, InotifyEvent, WatchDescriptor};
+use tokio::io::unix::AsyncFd;
+
+use persist_core::daemon;
+use persist_core::error::Error;
+use persist_core::protocol::WatchSpec;
+
+/// The file events that count as changes.
+fn watched_events() -> AddWatchFlags {
+    AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_MODIFY
+        | AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_MOVED_FROM
+        | AddWatchFlags::IN_MOVED_TO
+}
+
+/// An inotify instance, registered with the runtime to be read from asynchronously.
+struct Instance(Inotify);
+
+impl AsRawFd for Instance {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_fd().as_raw_fd()
+    }
+}
+
+/// Builds a set of globs, rejecting the invalid ones.
+fn build_globs<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Result<GlobSet, Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|err| Error::from(format!("invalid glob '{}': {}", pattern, err)))?;
+        builder.add(glob);
+    }
+
+    builder
+        .build()
+        .map_err(|err| Error::from(format!("invalid globs: {}", err)))
+}
+
+/// Watches files and directories (recursively) for changes, using inotify.
+pub struct Watcher {
+    inotify: AsyncFd<Instance>,
+    /// The watched paths, by their watch descriptor.
+    watched: HashMap<WatchDescriptor, PathBuf>,
+    /// The names of the files that are watched through their directory (which is not watched as a whole),
+    /// by the watch descriptor of that directory.
+    files: HashMap<WatchDescriptor, BTreeSet<OsString>>,
+    /// The directory that reported paths (and globs) are relative to.
+    root: PathBuf,
+    include: GlobSet,
+    ignore: GlobSet,
+    /// The directories not to watch at all, because everything within them is ignored.
+    ignored_dirs: GlobSet,
+    /// The daemon's home directory, whose contents (like log files) never count as changes.
+    home_dir: Option<PathBuf>,
+}
+
+impl Watcher {
+    /// Starts watching the files of a process, from its working directory.
+    fn new(spec: &WatchSpec, cwd: &Path) -> Result<Watcher, Error> {
+        let include = build_globs(spec.include.iter().map(String::as_str))?;
+        let ignore = build_globs(spec.ignore.iter().map(String::as_str))?;
+        //? patterns like `target/**` also exclude the directory itself from getting watched.
+        let ignored_dirs = build_globs(
+            spec.ignore
+                .iter()
+                .map(|pattern| pattern.strip_suffix("/**").unwrap_or(pattern.as_str())),
+        )?;
+
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        let home_dir = daemon::home_dir()
+            .ok()
+            .and_then(|path| path.canonicalize().ok());
+
+        //? the instance owns its file descriptor, so it stays open (and the same) for as long as it is registered.
+        let inotify = unsafe { AsyncFd::register(Instance(inotify)) }
+            .map_err(|err| Error::from(err.into_parts().1))?;
+
+        let mut watcher = Watcher {
+            inotify,
+            watched: HashMap::new(),
+            files: HashMap::new(),
+            root: cwd.to_path_buf(),
+            include,
+            ignore,
+            ignored_dirs,
+            home_dir,
+        };
+
+        for path in spec.paths.iter() {
+            let path = cwd.join(path);
+            watcher.add(&path).map_err(|err| {
+                Error::from(format!("could not watch '{}': {}", path.display(), err))
+            })?;
+        }
+
+        Ok(watcher)
+    }
+
+    /// Starts watching the files of a process, walking its directories on a blocking thread.
+    pub async fn build(spec: &WatchSpec, cwd: &Path) -> Result<Watcher, Error> {
+        let (spec, cwd) = (spec.clone(), cwd.to_path_buf());
+        tokio::task::spawn_blocking(move || Watcher::new(&spec, &cwd))
+            .await
+            .map_err(|err| Error::from(err.to_string()))?
+    }
+
+    /// Gets a path relative to the root directory (if it is within it).
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Whether changes to the given path are ignored.
+    fn is_ignored(&self, path: &Path) -> bool {
+        if self
+            .home_dir
+            .as_ref()
+            .is_some_and(|home_dir| path.starts_with(home_dir))
+        {
+            return true;
+        }
+
+        let relative = self.relative(path);
+        if self.ignore.is_match(relative) {
+            return true;
+        }
+
+        !self.include.is_empty() && !self.include.is_match(relative)
+    }
+
+    /// Watches a path, along with all the directories within it (unless they are ignored).
+    fn add(&mut self, path: &Path) -> Result<(), Error> {
+        let in_home = self
+            .home_dir
+            .as_ref()
+            .is_some_and(|home_dir| path.starts_with(home_dir));
+        if in_home || self.ignored_dirs.is_match(self.relative(path)) {
+            return Ok(());
+        }
+
+        //? single files are watched through their directory, for the watch to survive them getting replaced
+        //? (like editors do when saving, by writing another file and renaming it over the original one).
+        if !std::fs::metadata(path)?.is_dir() {
+            return self.add_file(path);
+        }
+
+        let descriptor = self.inotify.get_ref().0.add_watch(path, watched_events())?;
+        self.files.remove(&descriptor);
+        self.watched.insert(descriptor, path.to_path_buf());
+
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return Ok(());
+        };
+
+        //? the directories within may vanish while being walked, which is not an error.
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                let _ = self.add(&entry.path());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Watches a single file, by watching its directory for changes to entries of that name.
+    fn add_file(&mut self, path: &Path) -> Result<(), Error> {
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(Error::from(format!("'{}' is not a file", path.display())));
+        };
+
+        let descriptor = self.inotify.get_ref().0.add_watch(dir, watched_events())?;
+        let fully_watched =
+            self.watched.contains_key(&descriptor) && !self.files.contains_key(&descriptor);
+        if !fully_watched {
+            self.files
+                .entry(descriptor)
+                .or_default()
+                .insert(name.to_os_string());
+            self.watched.insert(descriptor, dir.to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next available events.
+    async fn read(&self) -> Result<Vec<InotifyEvent>, Error> {
+        loop {
+            let mut guard = self.inotify.readable().await?;
+            match guard.try_io(|inotify| inotify.get_ref().0.read_events().map_err(io::Error::from))
+            {
+                Ok(events) => return Ok(events?),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Gets the changed path that an event reports (if it is a relevant change),
+    /// starting to watch the directories that got created along the way.
+    fn process(&mut self, event: InotifyEvent) -> Option<PathBuf> {
+        if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+            self.watched.remove(&event.wd);
+            self.files.remove(&event.wd);
+            return None;
+        }
+
+        if let Some(files) = self.files.get(&event.wd) {
+            let name = event.name.as_ref()?;
+            if !files.contains(name) {
+                return None;
+            }
+        }
+
+        let base = self.watched.get(&event.wd)?;
+        let path = match event.name.as_ref() {
+            Some(name) => base.join(name),
+            None => base.clone(),
+        };
+
+        let created = AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO;
+        if event.mask.contains(AddWatchFlags::IN_ISDIR) && event.mask.intersects(created) {
+            let _ = self.add(&path);
+        }
+
+        if self.is_ignored(&path) {
+            return None;
+        }
+
+        Some(self.relative(&path).to_path_buf())
+    }
+
+    /// Waits for the next batch of changes, returning the changed paths.
+    ///
+    /// A batch ends once no more changes happened for the given delay, so that a single build or save
+    /// (touching many files, or the same file many times) only counts as a single batch.
+    pub async fn changes(&mut self, debounce: Duration) -> Result<Vec<PathBuf>, Error> {
+        let mut changed = BTreeSet::new();
+        loop {
+            let events = match changed.is_empty() {
+                true => self.read().await?,
+                false => match tokio::time::timeout(debounce, self.read()).await {
+                    Ok(events) => events?,
+                    Err(_) => return Ok(changed.into_iter().collect()),
+                },
+            };
+
+            for event in events {
+                if let Some(path) = self.process(event) {
+                    changed.insert(path);
+                }
+            }
+        }
+    }
+}
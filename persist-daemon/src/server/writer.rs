@@ -0,0 +1,188 @@
+use std::ffi::OsString;
+use std::fs::File as StdFile;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+
+use persist_core::error::Error;
+use persist_core::protocol::LogRotationSpec;
+
+/// Gets the path of the n-th rotated file of a log file (`<file>.<n>`, or `<file>.<n>.gz` if compressed).
+pub fn rotated_path(path: &Path, index: u32, compressed: bool) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    if compressed {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+/// Gets the paths of every rotated file that may be retained for a log file.
+pub fn rotated_paths(path: &Path, rotation: &LogRotationSpec) -> Vec<PathBuf> {
+    (1..=rotation.retain)
+        .flat_map(|index| {
+            let plain = rotated_path(path, index, false);
+            let compressed = rotated_path(path, index, true);
+            vec![plain, compressed]
+        })
+        .collect()
+}
+
+/// Removes a file, ignoring the case where it does not exist.
+async fn remove_file(path: &Path) -> Result<(), Error> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Error::from(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Renames a file, ignoring the case where it does not exist.
+async fn rename_file(from: &Path, to: &Path) -> Result<(), Error> {
+    match tokio::fs::rename(from, to).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Error::from(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Compresses a file using gzip (as `<file>.gz`), and removes the original.
+async fn compress_file(path: PathBuf) -> Result<(), Error> {
+    tokio::task::spawn_blocking(move || {
+        let mut compressed = OsString::from(path.as_os_str());
+        compressed.push(".gz");
+
+        let mut input = StdFile::open(&path)?;
+        let output = StdFile::create(compressed)?;
+        let mut encoder = GzEncoder::new(output, Compression::default());
+        io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?;
+
+        std::fs::remove_file(&path)?;
+
+        Ok::<_, Error>(())
+    })
+    .await
+    .map_err(|err| Error::from(err.to_string()))?
+}
+
+/// A writer appending lines to a log file, rotating it according to the process' rotation settings.
+pub struct LogWriter {
+    path: PathBuf,
+    rotation: LogRotationSpec,
+    file: File,
+    /// The current size of the log file.
+    size: u64,
+    /// When the current log file got created.
+    created_at: SystemTime,
+    /// The compression of the last rotated file, running in the background.
+    compressing: Option<JoinHandle<()>>,
+}
+
+impl LogWriter {
+    /// Opens a log file for appending.
+    pub async fn open(path: impl Into<PathBuf>, rotation: LogRotationSpec) -> Result<Self, Error> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        let metadata = file.metadata().await?;
+        let created_at = metadata.created().unwrap_or_else(|_| SystemTime::now());
+
+        Ok(Self {
+            path,
+            rotation,
+            file,
+            created_at,
+            size: metadata.len(),
+            compressing: None,
+        })
+    }
+
+    /// Appends a line to the log file, rotating it beforehand if needed.
+    pub async fn write_line(&mut self, line: &str) -> Result<(), Error> {
+        let len = line.len() as u64 + 1;
+        if self.should_rotate(len) {
+            self.rotate().await?;
+        }
+
+        let mut buffer = Vec::with_capacity(len as usize);
+        buffer.extend_from_slice(line.as_bytes());
+        buffer.push(b'\n');
+        self.file.write_all(&buffer).await?;
+        self.size += len;
+
+        Ok(())
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        //? never rotate empty files, which could otherwise happen with lines larger than the size limit.
+        if self.size == 0 {
+            return false;
+        }
+
+        let too_large =
+            matches!(self.rotation.max_size, Some(max_size) if self.size + incoming > max_size);
+        let too_old = self.rotation.max_age.is_some_and(|max_age| {
+            let age = self.created_at.elapsed().unwrap_or_default();
+            age >= Duration::from_millis(max_age)
+        });
+
+        too_large || too_old
+    }
+
+    /// Rotates the log file, shifting (and pruning) the previously rotated ones.
+    async fn rotate(&mut self) -> Result<(), Error> {
+        self.file.flush().await?;
+
+        //? the previously rotated file must be done getting compressed before being shifted.
+        if let Some(compressing) = self.compressing.take() {
+            let _ = compressing.await;
+        }
+
+        let retain = self.rotation.retain;
+        if retain == 0 {
+            remove_file(&self.path).await?;
+        } else {
+            for index in (1..=retain).rev() {
+                for compressed in [false, true] {
+                    let from = rotated_path(&self.path, index, compressed);
+                    if index == retain {
+                        remove_file(&from).await?;
+                    } else {
+                        let to = rotated_path(&self.path, index + 1, compressed);
+                        rename_file(&from, &to).await?;
+                    }
+                }
+            }
+
+            let rotated = rotated_path(&self.path, 1, false);
+            tokio::fs::rename(&self.path, &rotated).await?;
+            //? compressing is left to a background task, for the output of the process to keep being forwarded meanwhile.
+            if self.rotation.compress {
+                self.compressing = Some(tokio::spawn(async move {
+                    if let Err(err) = compress_file(rotated.clone()).await {
+                        eprintln!("could not compress '{}': {}", rotated.display(), err);
+                    }
+                }));
+            }
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        self.size = 0;
+        self.created_at = SystemTime::now();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use persist_core::deps;
+use persist_core::error::Error;
+use persist_core::protocol::{
+    DeleteRequest, DumpRequest, JobSchedule, ProcessSpec, ProcessStatus, RestartRequest,
+    ScaleRequest, StartRequest, StopRequest,
+};
+
+use crate::daemon;
+use crate::daemon::client::DaemonClient;
+use crate::format;
+use crate::project::{Project, PROJECT_FILES};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
+pub struct Opts {
+    /// The path of the project file (looked up next to the `.persist` directory by default)
+    #[structopt(short, long)]
+    pub file: Option<PathBuf>,
+    /// Only print the changes that would be made, without applying them
+    #[structopt(long)]
+    pub dry_run: bool,
+}
+
+/// A change to make to converge towards the project file.
+enum Action {
+    /// Start managing a newly declared process.
+    Create(StartRequest),
+    /// Recreate a process whose declaration changed.
+    Update(StartRequest, Vec<&'static str>),
+    /// Start an unchanged process which is not running.
+    Resume(String),
+    /// Stop an unchanged process which is declared as stopped.
+    Stop(String),
+    /// Change the number of instances of a process, whose declaration did not change otherwise.
+    Scale(String, usize),
+    /// Stop managing a process which is not declared anymore.
+    Delete(String),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Create(request) => {
+                write!(f, "{} {} (create)", "+".green().bold(), request.name)
+            }
+            Action::Update(request, changes) => write!(
+                f,
+                "{} {} (recreate, changed: {})",
+                "~".yellow().bold(),
+                request.name,
+                changes.join(", "),
+            ),
+            Action::Resume(name) => write!(f, "{} {} (start)", "~".yellow().bold(), name),
+            Action::Stop(name) => write!(f, "{} {} (stop)", "~".yellow().bold(), name),
+            Action::Scale(name, count) => write!(
+                f,
+                "{} {} (scale to {} instances)",
+                "~".yellow().bold(),
+                name,
+                count,
+            ),
+            Action::Delete(name) => write!(f, "{} {} (delete)", "-".red().bold(), name),
+        }
+    }
+}
+
+pub async fn handle(opts: Opts) -> Result<(), Error> {
+    let path = match opts.file {
+        Some(path) => path,
+        None => {
+            let home_dir = persist_core::daemon::home_dir()?;
+            let root = home_dir.parent().unwrap_or(home_dir.as_path());
+            Project::find(root).ok_or_else(|| {
+                format!(
+                    "could not find a project file ({}) in '{}'",
+                    PROJECT_FILES.join(", "),
+                    format::format_path(root),
+                )
+            })?
+        }
+    };
+    let path = path.canonicalize()?;
+    let root = path.parent().expect("file without parent directory");
+    let project = Project::load(&path).await?;
+
+    let mut daemon = daemon::connect().await?;
+    let current = daemon.dump(DumpRequest { filters: None }).await?;
+
+    //? the replicas of a process are compared to its declaration as a whole, through their first one.
+    let mut specs: Vec<&ProcessSpec> = current.iter().map(|response| &response.spec).collect();
+    specs.sort_by_key(|spec| (spec.group().to_string(), spec.instance));
+    specs.dedup_by(|spec, previous| spec.group() == previous.group());
+
+    let mut actions = Vec::new();
+    for spec in specs.iter() {
+        let name = spec.group().to_string();
+        let Some(decl) = project.processes.get(&name) else {
+            actions.push(Action::Delete(name));
+            continue;
+        };
+
+        let changes = decl.changes(spec, root);
+        //? scheduled jobs count as running, and one-shot jobs are done once they ran successfully.
+        let once = spec.job.as_ref().map(|job| &job.schedule) == Some(&JobSchedule::Once);
+        let running = match spec.status {
+            ProcessStatus::Running | ProcessStatus::Scheduled => true,
+            ProcessStatus::Exited => once,
+            _ => false,
+        };
+        let scaled = match (decl.instances.as_ref(), spec.instances.as_ref()) {
+            (Some(declared), Some(current)) => declared.count != current.count,
+            _ => false,
+        };
+        if scaled && changes == ["instances"] {
+            let count = decl
+                .instances
+                .as_ref()
+                .map_or(1, |instances| instances.count);
+            actions.push(Action::Scale(name, count));
+        } else if !changes.is_empty() {
+            let request = decl.to_request(&name, root)?;
+            actions.push(Action::Update(request, changes));
+        } else if decl.stopped && running {
+            actions.push(Action::Stop(name));
+        } else if !decl.stopped && !running {
+            actions.push(Action::Resume(name));
+        }
+    }
+    for (name, decl) in project.processes.iter() {
+        if specs.iter().all(|spec| spec.group() != name) {
+            actions.push(Action::Create(decl.to_request(name, root)?));
+        }
+    }
+
+    //? processes are stopped before the ones they depend on, and started after them.
+    let graph = project
+        .processes
+        .iter()
+        .map(|(name, decl)| {
+            let deps = decl.depends_on.iter().map(|dep| dep.name.clone()).collect();
+            (name.clone(), deps)
+        })
+        .collect();
+    let order = deps::order(&graph)?;
+    let rank = |name: &str| {
+        let position = order.iter().position(|it| it == name);
+        position.unwrap_or(order.len())
+    };
+    actions.sort_by_key(|action| match action {
+        Action::Delete(name) | Action::Stop(name) => (0, order.len() - rank(name)),
+        Action::Create(request) | Action::Update(request, _) => (1, rank(&request.name)),
+        Action::Resume(name) | Action::Scale(name, _) => (1, rank(name)),
+    });
+
+    if actions.is_empty() {
+        format::success("processes are already up-to-date with the project file.");
+        return Ok(());
+    }
+
+    format::info(format!(
+        "plan to apply '{}':",
+        format::format_path(&path).bold(),
+    ));
+    for action in actions.iter() {
+        println!("    {}", action);
+    }
+
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    for action in actions {
+        apply(&mut daemon, action).await;
+    }
+
+    Ok(())
+}
+
+/// Performs a single action, reporting its outcome.
+async fn apply(daemon: &mut DaemonClient, action: Action) {
+    let (name, outcome, verb) = match action {
+        Action::Create(request) => {
+            let name = request.name.clone();
+            let outcome = daemon.start(request).await.map(drop);
+            (name, outcome, "created")
+        }
+        Action::Update(request, _) => {
+            let name = request.name.clone();
+            let outcome = recreate(daemon, request).await;
+            (name, outcome, "recreated")
+        }
+        Action::Resume(name) => {
+            let request = RestartRequest {
+                filters: Some(vec![name.clone()]),
+                env: None,
+            };
+            let outcome = daemon.restart(request).await.and_then(|responses| {
+                first_error(responses.into_iter().map(|response| response.error))
+            });
+            (name, outcome, "started")
+        }
+        Action::Stop(name) => {
+            let request = StopRequest {
+                filters: Some(vec![name.clone()]),
+                signal: None,
+                timeout: None,
+            };
+            let outcome = daemon.stop(request).await.and_then(|responses| {
+                first_error(responses.into_iter().map(|response| response.error))
+            });
+            (name, outcome, "stopped")
+        }
+        Action::Scale(name, count) => {
+            let request = ScaleRequest {
+                name: name.clone(),
+                count,
+            };
+            let outcome = daemon.scale(request).await.map(drop);
+            (name, outcome, "scaled")
+        }
+        Action::Delete(name) => {
+            let request = DeleteRequest {
+                filters: Some(vec![name.clone()]),
+            };
+            let outcome = daemon.delete(request).await.and_then(|responses| {
+                first_error(responses.into_iter().map(|response| response.error))
+            });
+            (name, outcome, "deleted")
+        }
+    };
+
+    match outcome {
+        Ok(()) => format::success(format!("process '{}' successfully {}.", name, verb)),
+        Err(err) => format::error(format!("process '{}' could not be {}: {}", name, verb, err)),
+    }
+}
+
+/// Replaces a managed process with a new declaration of it.
+async fn recreate(daemon: &mut DaemonClient, request: StartRequest) -> Result<(), Error> {
+    let delete = DeleteRequest {
+        filters: Some(vec![request.name.clone()]),
+    };
+    let responses = daemon.delete(delete).await?;
+    first_error(responses.into_iter().map(|response| response.error))?;
+
+    daemon.start(request).await?;
+
+    Ok(())
+}
+
+/// Turns the first error reported by the daemon (if any) into an actual error.
+fn first_error(errors: impl Iterator<Item = Option<String>>) -> Result<(), Error> {
+    match errors.flatten().next() {
+        Some(error) => Err(Error::from(error)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use persist_core::daemon::SOCK_FILE;
+use persist_core::error::Error;
+use persist_core::protocol::{InfoRequest, InputRequest, LogsRequest, ResizeRequest};
+
+use crate::commands::logs::{self, Style};
+use crate::daemon::{self, client::DaemonClient};
+use crate::format;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
+pub struct Opts {
+    /// The name of the process to attach to
+    #[structopt(name = "process-name")]
+    pub name: String,
+    /// The number of previous log lines to initially output
+    #[structopt(long, short = "n", default_value = "10")]
+    pub lines: usize,
+    /// Show the time at which each log line got captured
+    #[structopt(long, short = "t")]
+    pub timestamps: bool,
+}
+
+/// The key detaching from a process running in a pseudo-terminal (Ctrl-]), as every other key gets sent to it.
+const DETACH_KEY: u8 = 0x1d;
+
+/// Keeps the terminal that the command runs in in raw mode, until dropped.
+///
+/// Keys like Ctrl-C then reach the process instead of being handled locally,
+/// and the input only gets echoed back by the pseudo-terminal of the process.
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Switches the terminal to raw mode, if the command runs in one.
+    fn enable() -> Option<RawMode> {
+        if unsafe { libc::isatty(libc::STDIN_FILENO) } == 0 {
+            return None;
+        }
+
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return None;
+        }
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        //? the output is still processed as usual, so that our own lines do not need carriage returns.
+        raw.c_oflag = original.c_oflag;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return None;
+        }
+
+        Some(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+/// Reads the terminal's input from a dedicated thread, sending it in chunks through the returned channel.
+///
+/// A thread is used (rather than `tokio::io::stdin`) so that a pending read never prevents the command from exiting.
+/// The channel gets closed once the input ends (as with Ctrl-D).
+fn read_input() -> mpsc::UnboundedReceiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut buffer = [0u8; 4096];
+        loop {
+            match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => {
+                    if sender.send(buffer[..count].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Gets the window size of the terminal that the command runs in (as rows and columns), if it runs in one.
+fn window_size() -> Option<(u16, u16)> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let outcome = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if outcome != 0 || size.ws_row == 0 || size.ws_col == 0 {
+        return None;
+    }
+
+    Some((size.ws_row, size.ws_col))
+}
+
+/// Gives the pseudo-terminal of the process the same window size as the terminal that the command runs in.
+async fn sync_window_size(client: &mut DaemonClient, name: &str) {
+    let Some((rows, cols)) = window_size() else {
+        return;
+    };
+
+    let request = ResizeRequest {
+        rows,
+        cols,
+        name: name.to_string(),
+    };
+    if let Err(err) = client.resize(request).await {
+        format::error(format!("{}.", err));
+    }
+}
+
+pub async fn handle(opts: Opts) -> Result<(), Error> {
+    let mut daemon = daemon::connect().await?;
+    let info = daemon
+        .info(InfoRequest {
+            name: opts.name.clone(),
+        })
+        .await?
+        .info;
+
+    let terminal = info.pty.is_some();
+    if !info.stdin && !terminal {
+        return Err(Error::from(format!(
+            "process '{}' does not accept input (it must be started with `--stdin` or `--pty`)",
+            info.name
+        )));
+    }
+
+    //? the logs are streamed over the first connection, so the input is sent over another one.
+    let socket_path = persist_core::daemon::home_dir()?.join(SOCK_FILE);
+    let mut input = DaemonClient::new(socket_path).await?;
+
+    //? processes running in a pseudo-terminal get every key (including Ctrl-D), except the detach key.
+    let raw_mode = match terminal {
+        true => RawMode::enable(),
+        false => None,
+    };
+    let detach_key = match raw_mode {
+        Some(_) => "Ctrl-]",
+        None => "Ctrl-D",
+    };
+    format::info(format!(
+        "attached to '{}' (press {} to detach).",
+        info.name, detach_key
+    ));
+
+    let request = LogsRequest {
+        filters: Some(vec![info.name.clone()]),
+        source_filter: None,
+        stream: true,
+        lines: opts.lines,
+        since: None,
+        until: None,
+        resume_on_lag: false,
+        search: None,
+        level: None,
+        fields: BTreeMap::new(),
+    };
+    let style = Style {
+        timestamps: opts.timestamps,
+        ..Style::default()
+    };
+
+    //? the pseudo-terminal of the process follows the size of the terminal it is attached to.
+    let mut resizes = signal(SignalKind::window_change())?;
+    let mut chunks = read_input();
+    let forward_input = async {
+        if terminal {
+            sync_window_size(&mut input, &info.name).await;
+        }
+        loop {
+            tokio::select! {
+                chunk = chunks.recv() => {
+                    let Some(mut data) = chunk else {
+                        break;
+                    };
+                    let detach = match raw_mode {
+                        Some(_) => data.iter().position(|byte| *byte == DETACH_KEY),
+                        None => None,
+                    };
+                    if let Some(index) = detach {
+                        data.truncate(index);
+                    }
+                    if !data.is_empty() {
+                        let request = InputRequest {
+                            name: info.name.clone(),
+                            data,
+                        };
+                        if let Err(err) = input.input(request).await {
+                            format::error(format!("{}.", err));
+                        }
+                    }
+                    if detach.is_some() {
+                        break;
+                    }
+                }
+                _ = resizes.recv(), if terminal => {
+                    sync_window_size(&mut input, &info.name).await;
+                }
+            }
+        }
+    };
+
+    tokio::select! {
+        outcome = logs::follow(daemon, request, style) => outcome,
+        _ = forward_input => {
+            format::info(format!("detached from '{}'.", opts.name));
+            Ok(())
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use colored::Colorize;
+use humansize::{format_size, BINARY};
 use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
 use prettytable::Table;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 use persist_core::error::Error;
-use persist_core::protocol::{InfoRequest, InfoResponse, ProcessStatus};
+use persist_core::protocol::{InfoRequest, InfoResponse};
 
 use crate::daemon;
+use crate::format;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
 pub struct Opts {
@@ -31,10 +32,7 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
         .build();
     table.set_format(table_fmt);
 
-    let status = match info.status {
-        ProcessStatus::Running => "running".green().bold(),
-        ProcessStatus::Stopped => "stopped".red().bold(),
-    };
+    let status = format::format_status(&info.status, info.exit.as_ref());
     let pid = match info.pid {
         Some(pid) => pid.to_string(),
         None => "none".to_string(),
@@ -44,6 +42,126 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
     table.add_row(row![b -> "Name", info.name]);
     table.add_row(row![b -> "Status", status]);
     table.add_row(row![b -> "PID", pid]);
+    table.add_row(row![b -> "Restart policy", info.restart.policy]);
+    table.add_row(row![b -> "Restarts", info.restarts]);
+    table.add_row(row![b -> "Stop signal", info.stop_signal]);
+    table.add_row(row![b -> "Stop timeout", format::format_duration(info.stop_timeout)]);
+    if let Some(check) = info.health_check.as_ref() {
+        let health_check = format!(
+            "{} (every {}, unhealthy after {} failures{})",
+            check.probe,
+            format::format_duration(check.interval),
+            check.retries,
+            if check.restart {
+                ", then restarted"
+            } else {
+                ""
+            },
+        );
+        table.add_row(row![b -> "Health check", health_check]);
+    }
+    if let Some(health) = info.health.as_ref() {
+        let status = format::format_health(health.status);
+        let status = match health.error.as_ref() {
+            Some(error) => format!("{} (last check failed: {})", status, error),
+            None => status.to_string(),
+        };
+        table.add_row(row![b -> "Health", status]);
+    }
+    if let Some(job) = info.job.as_ref() {
+        table.add_row(row![b -> "Job", job]);
+    }
+    if let Some(runs) = info.runs.as_ref() {
+        let last_run = format::format_last_run(runs, info.exit.as_ref());
+        table.add_row(row![b -> "Last run", last_run]);
+        if let Some(next_run) = runs.next_run {
+            let next_run = next_run.format("%Y-%m-%d %H:%M:%S").to_string();
+            let next_run = match runs.queued {
+                true => format!("{} (another run is queued)", next_run),
+                false => next_run,
+            };
+            table.add_row(row![b -> "Next run", next_run]);
+        }
+        if runs.skipped > 0 {
+            table.add_row(row![b -> "Skipped runs", runs.skipped]);
+        }
+    }
+    if let (Some(instances), Some(index)) = (info.instances.as_ref(), info.instance) {
+        let instance = match instances.port_of(index) {
+            Some(port) => format!(
+                "{} (out of {} instances, {}={})",
+                index, instances.count, instances.port_env, port
+            ),
+            None => format!("{} (out of {} instances)", index, instances.count),
+        };
+        table.add_row(row![b -> "Instance", instance]);
+    }
+    if let Some(watch) = info.watch.as_ref() {
+        table.add_row(row![b -> "Watch", watch]);
+    }
+    if let Some(trigger) = info.last_change.as_ref() {
+        let last_change = format!(
+            "{} (at {})",
+            trigger,
+            trigger.changed_at.format("%Y-%m-%d %H:%M:%S"),
+        );
+        table.add_row(row![b -> "Last change", last_change]);
+    }
+    if info.log_rotation.enabled() {
+        let rotation = &info.log_rotation;
+        let mut triggers = Vec::new();
+        if let Some(max_size) = rotation.max_size {
+            triggers.push(format!("above {}", format_size(max_size, BINARY)));
+        }
+        if let Some(max_age) = rotation.max_age {
+            triggers.push(format!("every {}", format::format_duration(max_age)));
+        }
+        let log_rotation = format!(
+            "{} (keeping {} files{})",
+            triggers.join(" or "),
+            rotation.retain,
+            if rotation.compress {
+                ", compressed"
+            } else {
+                ""
+            },
+        );
+        table.add_row(row![b -> "Log rotation", log_rotation]);
+    }
+    table.add_row(row![b -> "Log buffer", format!("{} lines", info.log_buffer)]);
+    if let Some(multiline) = info.multiline.as_ref() {
+        let multiline = format!(
+            "{} (up to {} lines, {}ms timeout)",
+            multiline.rule, multiline.max_lines, multiline.timeout,
+        );
+        table.add_row(row![b -> "Multiline", multiline]);
+    }
+    table.add_row(row![b -> "Log format", info.log_format]);
+    table.add_row(row![b -> "ANSI sequences", info.ansi]);
+    let stdin = if info.stdin { "kept open" } else { "empty" };
+    table.add_row(row![b -> "Stdin", stdin]);
+    if !info.depends_on.is_empty() {
+        let dependencies: Vec<String> = info.depends_on.iter().map(ToString::to_string).collect();
+        table.add_row(row![b -> "Depends on", dependencies.join(", ")]);
+    }
+    if let Some(pty) = info.pty.as_ref() {
+        table.add_row(row![b -> "Pseudo-terminal", format!("yes ({})", pty)]);
+    }
+    if !info.log_sinks.is_empty() {
+        let sinks: Vec<String> = info.log_sinks.iter().map(ToString::to_string).collect();
+        table.add_row(row![b -> "Log sinks", sinks.join("\n")]);
+    }
+    if let Some(exit) = info.exit.as_ref() {
+        let reason = if exit.stopped { "stopped" } else { "exited" };
+        let last_exit = format!(
+            "{} with {} at {} (ran for {})",
+            reason,
+            exit,
+            exit.exited_at.format("%Y-%m-%d %H:%M:%S"),
+            format::format_duration(exit.duration),
+        );
+        table.add_row(row![b -> "Last exit", last_exit]);
+    }
     table.add_row(row![b -> "Command", format!("{:?}", cmd)]);
     table.add_row(row![b -> "Args", format!("{:?}", args)]);
     table.add_row(row![b -> "Created at", info.created_at.format("%Y-%m-%d %H:%M:%S")]);
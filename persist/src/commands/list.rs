@@ -9,39 +9,74 @@ use persist_core::error::Error;
 use persist_core::protocol::{ListRequest, ProcessStatus};
 
 use crate::daemon;
+use crate::format;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
-pub struct Opts {}
+pub struct Opts {
+    /// The names of the processes to list (all of them by default)
+    #[structopt(name = "process-name")]
+    pub processes: Vec<String>,
+}
+
+pub async fn handle(opts: Opts) -> Result<(), Error> {
+    let filters = match opts.processes.is_empty() {
+        true => None,
+        false => Some(opts.processes),
+    };
 
-pub async fn handle(_: Opts) -> Result<(), Error> {
     let mut daemon = daemon::connect().await?;
-    let metrics = daemon.list(ListRequest { filters: None }).await?;
+    let metrics = daemon.list(ListRequest { filters }).await?;
+
+    //? the runs of jobs are only shown if some processes are jobs.
+    let jobs = metrics.iter().any(|metric| metric.job.is_some());
 
     let mut table = Table::new();
     table.set_format(*FORMAT_NO_LINESEP_WITH_TITLE);
-    table.set_titles(row![b => "Name", "PID", "Status", "CPU", "Memory"]);
+    let mut titles = row![b => "Name", "PID", "Status", "Health", "Restarts", "CPU", "Memory"];
+    if jobs {
+        titles.add_cell(cell!(b -> "Last run"));
+        titles.add_cell(cell!(b -> "Next run"));
+    }
+    table.set_titles(titles);
     if metrics.is_empty() {
-        table.add_row(row![bcH5 => "Empty list."]);
+        table.add_row(row![bcH7 => "Empty list."]);
     } else {
         for metric in metrics {
             let name = metric.name;
-            let status = match metric.status {
-                ProcessStatus::Running => "running".green().bold(),
-                ProcessStatus::Stopped => "stopped".red().bold(),
+            let status = format::format_status(&metric.status, metric.exit.as_ref());
+            let health = match metric.health {
+                Some(health) => format::format_health(health),
+                None => "N/A".normal(),
             };
             let cpu_usage = match metric.status {
                 ProcessStatus::Running => format!("{} %", metric.cpu_usage),
-                ProcessStatus::Stopped => "N/A".to_string(),
+                _ => "N/A".to_string(),
             };
             let mem_usage = match metric.status {
                 ProcessStatus::Running => format_size(metric.mem_usage, DECIMAL),
-                ProcessStatus::Stopped => "N/A".to_string(),
+                _ => "N/A".to_string(),
             };
             let pid = match metric.pid {
                 Some(pid) => pid.to_string(),
                 None => "none".to_string(),
             };
-            table.add_row(row![name, pid, status, cpu_usage, mem_usage]);
+            let restarts = metric.restarts;
+            let mut row = row![name, pid, status, health, restarts, cpu_usage, mem_usage];
+            if jobs {
+                let (last_run, next_run) = match metric.job.as_ref() {
+                    Some(runs) => (
+                        format::format_last_run(runs, metric.exit.as_ref()),
+                        match runs.next_run {
+                            Some(next_run) => next_run.format("%Y-%m-%d %H:%M:%S").to_string(),
+                            None => "none".to_string(),
+                        },
+                    ),
+                    None => ("N/A".to_string(), "N/A".to_string()),
+                };
+                row.add_cell(cell!(last_run));
+                row.add_cell(cell!(next_run));
+            }
+            table.add_row(row);
         }
     }
     table.printstd();
@@ -1,12 +1,18 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
 use colored::Colorize;
 use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
+use persist_core::ansi;
 use persist_core::error::Error;
-use persist_core::protocol::{LogStreamSource, LogsRequest, LogsResponse};
+use persist_core::protocol::{
+    LogLevel, LogSearch, LogStreamSource, LogsRequest, LogsResponse, StructuredLog,
+};
 
-use crate::daemon;
+use crate::daemon::{self, client::DaemonClient};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
 pub struct Opts {
@@ -16,7 +22,7 @@ pub struct Opts {
     /// Get logs from all the processes
     #[structopt(long)]
     pub all: bool,
-    /// The number of previous log lines to initially output
+    /// The number of previous log lines to initially output (merged chronologically across processes)
     #[structopt(long, short = "n", default_value = "10")]
     pub lines: usize,
     /// Disable log streaming
@@ -28,6 +34,134 @@ pub struct Opts {
     /// Only show logs from the process' stderr
     #[structopt(long)]
     pub err: bool,
+    /// Show the time at which each log line got captured
+    #[structopt(long, short = "t")]
+    pub timestamps: bool,
+    /// Only show logs captured since this time (as in "2024-01-31 12:00:00", or relative, as in "10m" or "2h")
+    #[structopt(long, parse(try_from_str = parse_time))]
+    pub since: Option<DateTime<Local>>,
+    /// Only show logs captured until this time (as in "2024-01-31 12:00:00", or relative, as in "10m" or "2h")
+    #[structopt(long, parse(try_from_str = parse_time))]
+    pub until: Option<DateTime<Local>>,
+    /// When falling behind the live output, recover the skipped lines from the log files
+    /// (instead of only reporting how many were skipped)
+    #[structopt(long)]
+    pub lossless: bool,
+    /// Only show log lines matching this regular expression (`-n` then being the number of previous matches)
+    #[structopt(long, short = "g", name = "PATTERN")]
+    pub grep: Option<String>,
+    /// Interpret the pattern as a plain substring, rather than a regular expression
+    #[structopt(long, short = "F")]
+    pub fixed_strings: bool,
+    /// Match the pattern case-insensitively
+    #[structopt(long, short = "i")]
+    pub ignore_case: bool,
+    /// The number of lines of context to also show after each matching line
+    #[structopt(long, short = "A", name = "AFTER")]
+    pub after_context: Option<usize>,
+    /// The number of lines of context to also show before each matching line
+    #[structopt(long, short = "B", name = "BEFORE")]
+    pub before_context: Option<usize>,
+    /// The number of lines of context to also show around each matching line
+    #[structopt(long, short = "C", name = "CONTEXT")]
+    pub context: Option<usize>,
+    /// Only show structured (JSON) log entries of at least this level (as in "warn")
+    #[structopt(long)]
+    pub level: Option<LogLevel>,
+    /// Only show structured (JSON) log entries having this value for this field (as in "user.id=42")
+    #[structopt(long = "field", value_name = "KEY=VALUE", number_of_values = 1, parse(try_from_str = parse_field))]
+    pub fields: Vec<(String, String)>,
+    /// Show structured (JSON) log entries as they got output, instead of pretty-printing them
+    #[structopt(long)]
+    pub raw: bool,
+    /// Disable colors, removing the ANSI escape sequences from the processes' output as well
+    #[structopt(long = "no-color")]
+    pub no_color: bool,
+}
+
+/// How the log entries get displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    /// Show the time at which each log line got captured.
+    pub timestamps: bool,
+    /// Show structured entries as they got output, instead of pretty-printing them.
+    pub raw: bool,
+    /// Remove the ANSI escape sequences from the processes' output.
+    pub no_color: bool,
+}
+
+/// Parses a field filter, formatted as `KEY=VALUE`.
+fn parse_field(value: &str) -> Result<(String, String), String> {
+    let (key, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid field filter (expected KEY=VALUE): '{}'", value))?;
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Pretty-prints a structured log entry, as in `WARN  message key=value ...`.
+fn format_structured(log: &StructuredLog) -> String {
+    let mut parts = Vec::new();
+    if let Some(time) = log.time.as_ref() {
+        parts.push(time.dimmed().to_string());
+    }
+    if let Some(level) = log.level {
+        let label = format!("{:5}", level.to_string().to_uppercase());
+        let label = match level {
+            LogLevel::Trace | LogLevel::Debug => label.dimmed(),
+            LogLevel::Info => label.green(),
+            LogLevel::Warn => label.yellow(),
+            LogLevel::Error | LogLevel::Fatal => label.red(),
+        };
+        parts.push(label.bold().to_string());
+    }
+    if let Some(message) = log.message.as_ref() {
+        parts.push(message.clone());
+    }
+    for (key, value) in log.fields.iter() {
+        let value = match value {
+            json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        parts.push(format!("{}={}", key.cyan(), value));
+    }
+
+    parts.join(" ")
+}
+
+/// Parses a point in time, either absolute (in local time, unless specified)
+/// or relative to now (as in "30s", "10m", "2h" or "1d" ago).
+fn parse_time(value: &str) -> Result<DateTime<Local>, String> {
+    let units = [('s', 1), ('m', 60), ('h', 3600), ('d', 86400)];
+    for (unit, secs) in units {
+        if let Some(Ok(amount)) = value.strip_suffix(unit).map(str::parse::<i64>) {
+            //? durations are kept in milliseconds, which must not overflow either.
+            return amount
+                .checked_mul(secs * 1000)
+                .map(Duration::milliseconds)
+                .and_then(|duration| Local::now().checked_sub_signed(duration))
+                .ok_or_else(|| format!("invalid time: '{}'", value));
+        }
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Local));
+    }
+
+    let formats = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"];
+    let naive = formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+            date.and_hms_opt(0, 0, 0)
+        })
+        .ok_or_else(|| format!("invalid time: '{}'", value))?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("invalid local time: '{}'", value))
 }
 
 pub async fn handle(opts: Opts) -> Result<(), Error> {
@@ -52,19 +186,86 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
         }
     };
 
+    if opts.no_color {
+        colored::control::set_override(false);
+    }
+
+    let search = match opts.grep {
+        Some(pattern) => Some(LogSearch {
+            pattern,
+            literal: opts.fixed_strings,
+            ignore_case: opts.ignore_case,
+            before: opts.before_context.or(opts.context).unwrap_or(0),
+            after: opts.after_context.or(opts.context).unwrap_or(0),
+        }),
+        None => None,
+    };
+
     let request = LogsRequest {
         filters,
         stream: !opts.no_stream,
         lines: opts.lines,
         source_filter,
+        since: opts.since,
+        until: opts.until,
+        resume_on_lag: opts.lossless,
+        search,
+        level: opts.level,
+        fields: opts.fields.iter().cloned().collect::<BTreeMap<_, _>>(),
+    };
+
+    let style = Style {
+        timestamps: opts.timestamps,
+        raw: opts.raw,
+        no_color: opts.no_color,
     };
 
-    let mut daemon = daemon::connect().await?;
+    let daemon = daemon::connect().await?;
+    follow(daemon, request, style).await
+}
+
+/// Prints the log entries (and lifecycle events) sent by the daemon for the given request, until it stops sending them.
+pub async fn follow(
+    mut daemon: DaemonClient,
+    request: LogsRequest,
+    style: Style,
+) -> Result<(), Error> {
     let mut logs = daemon.logs(request).await?;
 
     while let Some(response) = logs.next().await.transpose()? {
         let entry = match response {
             LogsResponse::Entry(entry) => entry,
+            LogsResponse::Gap(gap) => {
+                let source = match gap.source {
+                    LogStreamSource::Stdout => "(out)",
+                    LogStreamSource::Stderr => "(err)",
+                };
+                let msg = format!(
+                    "... {} lines skipped (too slow to keep up) ...",
+                    gap.skipped
+                );
+                println!(
+                    " {} {} {} {}",
+                    gap.name.bright_blue().bold(),
+                    source.bold(),
+                    "|".bold(),
+                    msg.yellow().bold(),
+                );
+                continue;
+            }
+            LogsResponse::Event(event) => {
+                if style.timestamps {
+                    let timestamp = event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f");
+                    print!(" {}", timestamp.to_string().dimmed());
+                }
+                let marker = format!("--- {} ---", event.event);
+                println!(
+                    " {} {}",
+                    event.name.bright_blue().bold(),
+                    marker.cyan().bold()
+                );
+                continue;
+            }
             LogsResponse::Unsubscribed => break,
             LogsResponse::Subscribed => {
                 // TODO(error): received `Subscribed` twice ??
@@ -77,14 +278,101 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
             LogStreamSource::Stderr => "(err)",
         };
 
-        println!(
-            " {} {} {} {}",
-            entry.name.bright_blue().bold(),
-            source.bold(),
-            "|".bold(),
-            entry.msg,
-        );
+        let timestamp = match (style.timestamps, entry.timestamp) {
+            (false, _) => String::new(),
+            (true, Some(timestamp)) => timestamp.format(" %Y-%m-%d %H:%M:%S%.3f").to_string(),
+            (true, None) => format!(" {:23}", "-"),
+        };
+
+        //? context lines around search matches are told apart like `grep` does.
+        let separator = match entry.context {
+            true => "-",
+            false => "|",
+        };
+
+        //? the lines of multi-line entries are aligned under the first one.
+        let msg = match entry.structured.as_ref() {
+            Some(log) if !style.raw => format_structured(log),
+            _ => entry.msg,
+        };
+        let msg = match style.no_color {
+            true => ansi::strip(&msg).into_owned(),
+            false => msg,
+        };
+        for (index, line) in msg.split('\n').enumerate() {
+            let msg = match entry.context {
+                true => line.dimmed(),
+                false => line.normal(),
+            };
+            if index == 0 {
+                println!(
+                    "{} {} {} {} {}",
+                    timestamp.dimmed(),
+                    entry.name.bright_blue().bold(),
+                    source.bold(),
+                    separator.bold(),
+                    msg,
+                );
+            } else {
+                let padding = timestamp.len() + entry.name.len() + source.len() + 2;
+                println!("{:padding$} {} {}", "", separator.bold(), msg);
+            }
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_times() {
+        let before = Local::now();
+        let time = parse_time("90s").unwrap();
+        let after = Local::now();
+        assert!(time >= before - Duration::seconds(90) && time <= after - Duration::seconds(90));
+
+        let ago = |value: &str| {
+            let time = parse_time(value).unwrap();
+            (Local::now() - time).num_minutes()
+        };
+        assert_eq!(ago("10m"), 10);
+        assert_eq!(ago("2h"), 120);
+        assert_eq!(ago("1d"), 1440);
+        assert_eq!(ago("0s"), 0);
+    }
+
+    #[test]
+    fn absolute_times() {
+        let expected = Local.with_ymd_and_hms(2024, 1, 31, 12, 30, 15).unwrap();
+        assert_eq!(parse_time("2024-01-31 12:30:15"), Ok(expected));
+        assert_eq!(parse_time("2024-01-31T12:30:15"), Ok(expected));
+
+        let minutes = Local.with_ymd_and_hms(2024, 1, 31, 12, 30, 0).unwrap();
+        assert_eq!(parse_time("2024-01-31 12:30"), Ok(minutes));
+
+        let midnight = Local.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap();
+        assert_eq!(parse_time("2024-01-31"), Ok(midnight));
+    }
+
+    #[test]
+    fn times_with_offsets() {
+        let time = parse_time("2024-01-31T12:30:15Z").unwrap();
+        let expected = DateTime::parse_from_rfc3339("2024-01-31T14:30:15+02:00").unwrap();
+        assert_eq!(time, expected);
+    }
+
+    #[test]
+    fn invalid_times() {
+        assert!(parse_time("").is_err());
+        assert!(parse_time("yesterday").is_err());
+        assert!(parse_time("10y").is_err());
+        assert!(parse_time("2024-13-01").is_err());
+        assert!(parse_time("2024-01-31 25:00:00").is_err());
+        //? durations too large to be represented are rejected instead of overflowing.
+        assert!(parse_time("9223372036854775807s").is_err());
+        assert!(parse_time("100000000000d").is_err());
+    }
+}
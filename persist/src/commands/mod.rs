@@ -1,3 +1,5 @@
+pub mod apply;
+pub mod attach;
 pub mod delete;
 pub mod dump;
 pub mod info;
@@ -7,5 +9,6 @@ pub mod logs;
 pub mod prune;
 pub mod restart;
 pub mod restore;
+pub mod scale;
 pub mod start;
 pub mod stop;
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use persist_core::error::Error;
+use persist_core::protocol::ScaleRequest;
+
+use crate::daemon;
+use crate::format;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
+pub struct Opts {
+    /// The name of the process to scale (started with `--instances`)
+    #[structopt(name = "process-name")]
+    pub name: String,
+    /// The number of instances to run
+    pub count: usize,
+}
+
+pub async fn handle(opts: Opts) -> Result<(), Error> {
+    let mut daemon = daemon::connect().await?;
+    let request = ScaleRequest {
+        name: opts.name,
+        count: opts.count,
+    };
+    let response = daemon.scale(request).await?;
+
+    for name in response.created.iter() {
+        format::success(format!("process '{}' successfully created.", name));
+    }
+    for name in response.deleted.iter() {
+        format::success(format!("process '{}' successfully deleted.", name));
+    }
+    format::success(format!(
+        "process '{}' successfully scaled to {} instances.",
+        response.name, opts.count
+    ));
+
+    Ok(())
+}
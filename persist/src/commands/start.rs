@@ -1,11 +1,15 @@
 use std::env;
+use std::path::PathBuf;
 
-use persist_core::protocol::ProcessStatus;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 use persist_core::error::Error;
-use persist_core::protocol::StartRequest;
+use persist_core::protocol::{
+    self, AnsiMode, Dependency, HealthCheckSpec, HealthProbe, InstancesSpec, JobSchedule, JobSpec,
+    LogFormat, LogRotationSpec, LogSink, MultilineRule, MultilineSpec, OverlapPolicy,
+    ProcessStatus, PtySpec, RestartPolicy, RestartSpec, StartRequest, WatchSpec,
+};
 
 use crate::daemon;
 use crate::format;
@@ -18,10 +22,183 @@ pub struct Opts {
     /// Insert the process, but do not start it immediately
     #[structopt(long)]
     pub stopped: bool,
+    /// When to automatically restart the process once it exits ("never", "on-failure" or "always")
+    #[structopt(long, default_value = "never")]
+    pub restart: RestartPolicy,
+    /// The maximum number of consecutive automatic restarts (unlimited by default)
+    #[structopt(long)]
+    pub max_retries: Option<u32>,
+    /// The delay before the first automatic restart, in milliseconds (doubled on each consecutive attempt)
+    #[structopt(long, default_value = "1000")]
+    pub restart_delay: u64,
+    /// The maximum delay between automatic restarts, in milliseconds
+    #[structopt(long, default_value = "60000")]
+    pub max_restart_delay: u64,
+    /// The signal to send to the process to stop it
+    #[structopt(long, default_value = "SIGTERM", parse(try_from_str = parse_signal_name))]
+    pub stop_signal: String,
+    /// The time given to the process to exit once signaled to stop, in milliseconds (killed afterwards)
+    #[structopt(long, default_value = "10000")]
+    pub stop_timeout: u64,
+    /// Check the health of the process by running a shell command, which must succeed
+    #[structopt(long, conflicts_with_all = &["health-tcp", "health-http"])]
+    pub health_exec: Option<String>,
+    /// Check the health of the process by connecting to a local TCP port
+    #[structopt(long, conflicts_with = "health-http")]
+    pub health_tcp: Option<u16>,
+    /// Check the health of the process by sending a GET request to a local HTTP endpoint (as in "8080/healthz")
+    #[structopt(long, parse(try_from_str = parse_http_probe))]
+    pub health_http: Option<(u16, String)>,
+    /// The delay between two consecutive health checks, in milliseconds
+    #[structopt(long, default_value = "10000")]
+    pub health_interval: u64,
+    /// The time given to a single health check to succeed, in milliseconds
+    #[structopt(long, default_value = "5000")]
+    pub health_timeout: u64,
+    /// The number of consecutive failed health checks after which the process is considered unhealthy
+    #[structopt(long, default_value = "3")]
+    pub health_retries: u32,
+    /// Restart the process once it becomes unhealthy
+    #[structopt(long)]
+    pub health_restart: bool,
+    /// Rotate the log files once they exceed this size, in bytes (accepts "K", "M" and "G" suffixes)
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub log_max_size: Option<u64>,
+    /// Rotate the log files once they are older than this, in milliseconds
+    #[structopt(long)]
+    pub log_max_age: Option<u64>,
+    /// The number of rotated log files to keep
+    #[structopt(long, default_value = "5")]
+    pub log_retain: u32,
+    /// Compress rotated log files (using gzip)
+    #[structopt(long)]
+    pub log_compress: bool,
+    /// The number of log lines buffered for live log subscribers, per output stream
+    #[structopt(long, default_value = "1024")]
+    pub log_buffer: usize,
+    /// Group lines starting with whitespace with the previous ones, into multi-line log entries (like stack traces)
+    #[structopt(long, conflicts_with = "multiline-start")]
+    pub multiline_indent: bool,
+    /// Group lines into multi-line log entries, each of them starting with a line matching this regular expression
+    #[structopt(long)]
+    pub multiline_start: Option<String>,
+    /// The delay after which a multi-line log entry is complete if no more lines arrive for it, in milliseconds
+    #[structopt(long, default_value = "500")]
+    pub multiline_timeout: u64,
+    /// The maximum number of lines grouped into a single multi-line log entry
+    #[structopt(long, default_value = "500")]
+    pub multiline_max_lines: usize,
+    /// The format of the lines that the process outputs ("text" or "json")
+    #[structopt(long, default_value = "text")]
+    pub log_format: LogFormat,
+    /// Also send the logs to the local syslog daemon
+    #[structopt(long)]
+    pub syslog: bool,
+    /// The path of the syslog socket
+    #[structopt(long, default_value = "/dev/log")]
+    pub syslog_socket: PathBuf,
+    /// The syslog facility to send the logs with (as in "user", "daemon" or "local0")
+    #[structopt(long, default_value = "user", parse(try_from_str = parse_facility_name))]
+    pub syslog_facility: String,
+    /// The application name to send the logs with to syslog (the name of the process, by default)
+    #[structopt(long)]
+    pub syslog_tag: Option<String>,
+    /// Also send the logs to the systemd journal
+    #[structopt(long)]
+    pub journald: bool,
+    /// The path of the journald native socket
+    #[structopt(long, default_value = "/run/systemd/journal/socket")]
+    pub journald_socket: PathBuf,
+    /// How to handle the ANSI escape sequences (colors, ...) that the process outputs
+    /// ("keep", "strip", or "live-only" to only strip them from the log files)
+    #[structopt(long, default_value = "keep")]
+    pub ansi: AnsiMode,
+    /// Keep the process' stdin open, to send input to it using `persist attach`
+    #[structopt(long)]
+    pub stdin: bool,
+    /// Run the process in a pseudo-terminal (its stdout and stderr then get captured together, as stdout)
+    #[structopt(long)]
+    pub pty: bool,
+    /// The initial window size of the pseudo-terminal, formatted as COLSxROWS
+    #[structopt(long, default_value = "80x24")]
+    pub pty_size: PtySpec,
+    /// A process to start before this one, and to wait for (as in "db", or "db:healthy" to wait for its health check)
+    #[structopt(long, value_name = "NAME[:CONDITION]", number_of_values = 1)]
+    pub depends_on: Vec<Dependency>,
+    /// How long to wait for the dependencies to meet their condition, in milliseconds
+    #[structopt(long, default_value = "60000")]
+    pub dependency_timeout: u64,
+    /// Run the process as a job, either "once" or on a cron schedule (as in "*/5 * * * *" or "@daily")
+    #[structopt(long, value_name = "SCHEDULE")]
+    pub job: Option<JobSchedule>,
+    /// What to do when the job is due while its previous run is still going ("skip", "queue" or "kill")
+    #[structopt(long, default_value = "skip")]
+    pub overlap: OverlapPolicy,
+    /// A file or directory to watch, to restart the process whenever it changes (directories are watched recursively)
+    #[structopt(long, number_of_values = 1)]
+    pub watch: Vec<PathBuf>,
+    /// Only restart the process on changes to files matching this glob (as in "src/**/*.rs")
+    #[structopt(long, value_name = "GLOB", number_of_values = 1)]
+    pub watch_include: Vec<String>,
+    /// Ignore changes to files matching this glob (as in "target/**")
+    #[structopt(long, value_name = "GLOB", number_of_values = 1)]
+    pub watch_ignore: Vec<String>,
+    /// The delay without further changes to wait for before restarting the process, in milliseconds
+    #[structopt(long, default_value = "500")]
+    pub watch_debounce: u64,
+    /// Run several instances of the process (named after it, as in "worker:0", "worker:1", ...)
+    #[structopt(long, value_name = "N")]
+    pub instances: Option<usize>,
+    /// Give each instance its own port, starting from this one and offset by the index of the instance
+    #[structopt(long, value_name = "BASE", requires = "instances")]
+    pub port: Option<u16>,
+    /// The environment variable to pass the port of each instance in
+    #[structopt(long, value_name = "NAME", default_value = "PORT")]
+    pub port_env: String,
     /// The command to launch
     pub command: Vec<String>,
 }
 
+/// Validates a signal name, normalizing it (as in `SIGTERM`).
+pub fn parse_signal_name(name: &str) -> Result<String, String> {
+    protocol::parse_signal(name).map(|signal| signal.to_string())
+}
+
+/// Validates a syslog facility name.
+fn parse_facility_name(name: &str) -> Result<String, String> {
+    protocol::parse_syslog_facility(name).map(|_| name.to_ascii_lowercase())
+}
+
+/// Parses an HTTP health check target, formatted as `PORT[/PATH]`.
+fn parse_http_probe(value: &str) -> Result<(u16, String), String> {
+    let (port, path) = match value.find('/') {
+        Some(idx) => value.split_at(idx),
+        None => (value, "/"),
+    };
+    let port = port
+        .parse()
+        .map_err(|_| format!("invalid port number: '{}'", port))?;
+
+    Ok((port, path.to_string()))
+}
+
+/// Parses a size in bytes, optionally suffixed by a unit (as in "512K", "10M" or "1G").
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, factor) = match value.to_ascii_uppercase().chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size: '{}'", value))?;
+
+    number
+        .checked_mul(factor)
+        .ok_or_else(|| format!("size too large: '{}'", value))
+}
+
 pub async fn handle(opts: Opts) -> Result<(), Error> {
     if opts.command.is_empty() {
         return Err(Error::from(String::from("empty commands not permitted")));
@@ -30,7 +207,7 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
     let cmd = opts.command;
     let name = match opts.name {
         Some(name) => name,
-        None => cmd[0].split('/').last().unwrap().to_string(),
+        None => cmd[0].split('/').next_back().unwrap().to_string(),
     };
     let cwd = env::current_dir()?;
     let cwd = cwd.canonicalize()?;
@@ -39,6 +216,96 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
         true => ProcessStatus::Stopped,
         false => ProcessStatus::Running,
     };
+    let restart = RestartSpec {
+        policy: opts.restart,
+        max_retries: opts.max_retries,
+        backoff: opts.restart_delay,
+        max_backoff: opts.max_restart_delay,
+    };
+    let probe = match (opts.health_exec, opts.health_tcp, opts.health_http) {
+        (Some(cmd), _, _) => Some(HealthProbe::Exec {
+            cmd: vec!["sh".to_string(), "-c".to_string(), cmd],
+        }),
+        (_, Some(port), _) => Some(HealthProbe::Tcp { port }),
+        (_, _, Some((port, path))) => Some(HealthProbe::Http { port, path }),
+        _ => None,
+    };
+    let health_check = match probe {
+        Some(probe) => Some(HealthCheckSpec {
+            probe,
+            interval: opts.health_interval,
+            timeout: opts.health_timeout,
+            retries: opts.health_retries,
+            restart: opts.health_restart,
+        }),
+        None => None,
+    };
+
+    let log_rotation = LogRotationSpec {
+        max_size: opts.log_max_size,
+        max_age: opts.log_max_age,
+        retain: opts.log_retain,
+        compress: opts.log_compress,
+    };
+
+    let rule = match (opts.multiline_indent, opts.multiline_start) {
+        (true, _) => Some(MultilineRule::Indent),
+        (_, Some(pattern)) => Some(MultilineRule::Start { pattern }),
+        _ => None,
+    };
+    let multiline = match rule {
+        Some(rule) => Some(MultilineSpec {
+            rule,
+            timeout: opts.multiline_timeout,
+            max_lines: opts.multiline_max_lines,
+        }),
+        None => None,
+    };
+
+    let mut log_sinks = Vec::new();
+    if opts.syslog {
+        log_sinks.push(LogSink::Syslog {
+            socket: opts.syslog_socket,
+            facility: opts.syslog_facility,
+            tag: opts.syslog_tag,
+        });
+    }
+    if opts.journald {
+        log_sinks.push(LogSink::Journald {
+            socket: opts.journald_socket,
+        });
+    }
+
+    let dependency_timeout = opts.dependency_timeout;
+    let depends_on = opts
+        .depends_on
+        .into_iter()
+        .map(|dependency| Dependency {
+            timeout: dependency_timeout,
+            ..dependency
+        })
+        .collect();
+
+    let overlap = opts.overlap;
+    let job = opts.job.map(|schedule| JobSpec { schedule, overlap });
+
+    let watch = match opts.watch.is_empty() {
+        true => None,
+        false => Some(WatchSpec {
+            paths: opts.watch,
+            include: opts.watch_include,
+            ignore: opts.watch_ignore,
+            debounce: opts.watch_debounce,
+        }),
+    };
+
+    let port = opts.port;
+    let port_env = opts.port_env;
+    let instances = opts.instances.map(|count| InstancesSpec {
+        count,
+        port,
+        port_env,
+    });
 
     let request = StartRequest {
         name,
@@ -46,10 +313,36 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
         cwd,
         env,
         status,
+        restart,
+        stop_signal: opts.stop_signal,
+        stop_timeout: opts.stop_timeout,
+        health_check,
+        log_rotation,
+        log_buffer: opts.log_buffer,
+        multiline,
+        log_format: opts.log_format,
+        log_sinks,
+        ansi: opts.ansi,
+        stdin: opts.stdin,
+        depends_on,
+        job,
+        watch,
+        instances,
+        pty: match opts.pty {
+            true => Some(opts.pty_size),
+            false => None,
+        },
     };
 
     let mut daemon = daemon::connect().await?;
-    let msg = format!("process '{}' successfully started.", request.name);
+    let mut msg = match request.job.as_ref().map(|job| &job.schedule) {
+        Some(JobSchedule::Cron(_)) => format!("process '{}' successfully scheduled", request.name),
+        _ => format!("process '{}' successfully started", request.name),
+    };
+    match request.instances.as_ref() {
+        Some(instances) => msg.push_str(format!(" ({} instances).", instances.count).as_str()),
+        None => msg.push('.'),
+    }
     daemon.start(request).await?;
     format::success(msg);
 
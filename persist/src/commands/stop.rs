@@ -4,6 +4,7 @@ use structopt::StructOpt;
 use persist_core::error::Error;
 use persist_core::protocol::StopRequest;
 
+use crate::commands::start::parse_signal_name;
 use crate::daemon;
 use crate::format;
 
@@ -15,6 +16,12 @@ pub struct Opts {
     /// The names of the processes to stop
     #[structopt(name = "process-name")]
     pub processes: Vec<String>,
+    /// The signal to send to the processes (overrides their configured stop signal)
+    #[structopt(long, parse(try_from_str = parse_signal_name))]
+    pub signal: Option<String>,
+    /// The time given to the processes to exit, in milliseconds, before killing them (overrides their configured stop timeout)
+    #[structopt(long)]
+    pub timeout: Option<u64>,
 }
 
 pub async fn handle(opts: Opts) -> Result<(), Error> {
@@ -29,7 +36,12 @@ pub async fn handle(opts: Opts) -> Result<(), Error> {
     };
 
     let mut daemon = daemon::connect().await?;
-    let responses = daemon.stop(StopRequest { filters }).await?;
+    let request = StopRequest {
+        filters,
+        signal: opts.signal,
+        timeout: opts.timeout,
+    };
+    let responses = daemon.stop(request).await?;
 
     for response in responses {
         if let Some(error) = response.error {
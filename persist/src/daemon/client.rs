@@ -20,13 +20,28 @@ impl DaemonClient {
         Ok(DaemonClient { socket: framed })
     }
 
-    pub async fn kill(&mut self) -> Result<(), Error> {
-        let request = Request::Kill;
+    pub async fn kill(&mut self, request: KillRequest) -> Result<KillResponse, Error> {
+        let request = Request::Kill(request);
         let serialized = json::to_string(&request)?;
 
         self.socket.send(serialized).await?;
 
-        Ok(())
+        let response = if let Some(response) = self.socket.next().await {
+            let response = response?;
+            json::from_str::<Response>(response.as_str())?
+        } else {
+            return Err(Error::from(String::from(
+                "daemon closed connection without responding",
+            )));
+        };
+
+        let response = match response {
+            Response::Killed(response) => response,
+            Response::Error(err) => return Err(Error::from(err)),
+            _ => return Err(Error::from(String::from("unexpected response from daemon"))),
+        };
+
+        Ok(response)
     }
 
     pub async fn version(&mut self) -> Result<VersionResponse, Error> {
@@ -78,7 +93,7 @@ impl DaemonClient {
     }
 
     pub async fn start(&mut self, request: StartRequest) -> Result<StartResponse, Error> {
-        let request = Request::Start(request);
+        let request = Request::Start(Box::new(request));
         let serialized = json::to_string(&request)?;
 
         self.socket.send(serialized).await?;
@@ -314,4 +329,72 @@ impl DaemonClient {
 
         Ok(response)
     }
+
+    pub async fn input(&mut self, request: InputRequest) -> Result<(), Error> {
+        let request = Request::Input(request);
+        let serialized = json::to_string(&request)?;
+
+        self.socket.send(serialized).await?;
+
+        let response = if let Some(response) = self.socket.next().await {
+            let response = response?;
+            json::from_str::<Response>(response.as_str())?
+        } else {
+            return Err(Error::from(String::from(
+                "daemon closed connection without responding",
+            )));
+        };
+
+        match response {
+            Response::Input => Ok(()),
+            Response::Error(err) => Err(Error::from(err)),
+            _ => Err(Error::from(String::from("unexpected response from daemon"))),
+        }
+    }
+
+    pub async fn resize(&mut self, request: ResizeRequest) -> Result<(), Error> {
+        let request = Request::Resize(request);
+        let serialized = json::to_string(&request)?;
+
+        self.socket.send(serialized).await?;
+
+        let response = if let Some(response) = self.socket.next().await {
+            let response = response?;
+            json::from_str::<Response>(response.as_str())?
+        } else {
+            return Err(Error::from(String::from(
+                "daemon closed connection without responding",
+            )));
+        };
+
+        match response {
+            Response::Resize => Ok(()),
+            Response::Error(err) => Err(Error::from(err)),
+            _ => Err(Error::from(String::from("unexpected response from daemon"))),
+        }
+    }
+
+    pub async fn scale(&mut self, request: ScaleRequest) -> Result<ScaleResponse, Error> {
+        let request = Request::Scale(request);
+        let serialized = json::to_string(&request)?;
+
+        self.socket.send(serialized).await?;
+
+        let response = if let Some(response) = self.socket.next().await {
+            let response = response?;
+            json::from_str::<Response>(response.as_str())?
+        } else {
+            return Err(Error::from(String::from(
+                "daemon closed connection without responding",
+            )));
+        };
+
+        let response = match response {
+            Response::Scale(response) => response,
+            Response::Error(err) => return Err(Error::from(err)),
+            _ => return Err(Error::from(String::from("unexpected response from daemon"))),
+        };
+
+        Ok(response)
+    }
 }
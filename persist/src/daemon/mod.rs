@@ -11,6 +11,7 @@ pub mod client;
 
 use persist_core::daemon::SOCK_FILE;
 use persist_core::error::Error;
+use persist_core::protocol::KillRequest;
 
 use crate::daemon::client::DaemonClient;
 use crate::format;
@@ -18,16 +19,39 @@ use crate::format;
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructOpt)]
 pub enum Opts {
     /// Kill the current daemon (will stop all managed processes)
-    Kill,
+    Kill {
+        /// Leave the managed processes running, to be re-adopted by the next daemon
+        /// (their output gets buffered meanwhile, up to the system's pipe capacity),
+        /// except the ones running in a pseudo-terminal
+        #[structopt(long)]
+        keep_processes: bool,
+    },
     /// Get version information about the current daemon
     Version,
 }
 
 pub async fn handle(opts: Opts) -> Result<(), Error> {
     match opts {
-        Opts::Kill => {
+        Opts::Kill { keep_processes } => {
             let mut daemon = self::connect().await?;
-            daemon.kill().await?;
+            let response = daemon.kill(KillRequest { keep_processes }).await?;
+
+            for response in response.stopped {
+                if let Some(error) = response.error {
+                    let msg = format!(
+                        "process '{}' could not be stopped: {}",
+                        response.name, error
+                    );
+                    format::error(msg);
+                } else {
+                    let msg = format!("process '{}' successfully stopped.", response.name);
+                    format::success(msg);
+                }
+            }
+            for name in response.kept {
+                format::info(format!("process '{}' left running.", name));
+            }
+
             format::success("daemon successfully killed.");
         }
         Opts::Version => {
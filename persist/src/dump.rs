@@ -3,7 +3,11 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-use persist_core::protocol::{ProcessSpec, ProcessStatus};
+use persist_core::protocol::{
+    default_log_buffer, default_stop_signal, default_stop_timeout, AnsiMode, Dependency,
+    HealthCheckSpec, InstancesSpec, JobSpec, LogFormat, LogRotationSpec, LogSink, MultilineSpec,
+    ProcessSpec, ProcessStatus, PtySpec, RestartSpec, WatchSpec,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProcessDump {
@@ -12,6 +16,40 @@ pub struct ProcessDump {
     pub cwd: PathBuf,
     pub env: HashMap<String, String>,
     pub status: ProcessStatus,
+    #[serde(default)]
+    pub restart: RestartSpec,
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: u64,
+    #[serde(default)]
+    pub health_check: Option<HealthCheckSpec>,
+    #[serde(default)]
+    pub log_rotation: LogRotationSpec,
+    #[serde(default = "default_log_buffer")]
+    pub log_buffer: usize,
+    #[serde(default)]
+    pub multiline: Option<MultilineSpec>,
+    #[serde(default)]
+    pub log_format: LogFormat,
+    #[serde(default)]
+    pub log_sinks: Vec<LogSink>,
+    #[serde(default)]
+    pub ansi: AnsiMode,
+    #[serde(default)]
+    pub stdin: bool,
+    #[serde(default)]
+    pub pty: Option<PtySpec>,
+    #[serde(default)]
+    pub depends_on: Vec<Dependency>,
+    #[serde(default)]
+    pub job: Option<JobSpec>,
+    #[serde(default)]
+    pub watch: Option<WatchSpec>,
+    #[serde(default)]
+    pub instances: Option<InstancesSpec>,
+    #[serde(default)]
+    pub instance: Option<usize>,
     pub pid_path: PathBuf,
     pub stdout_path: PathBuf,
     pub stderr_path: PathBuf,
@@ -26,6 +64,23 @@ impl From<ProcessSpec> for ProcessDump {
             cwd: spec.cwd,
             env: spec.env.into_iter().collect(),
             status: spec.status,
+            restart: spec.restart,
+            stop_signal: spec.stop_signal,
+            stop_timeout: spec.stop_timeout,
+            health_check: spec.health_check,
+            log_rotation: spec.log_rotation,
+            log_buffer: spec.log_buffer,
+            multiline: spec.multiline,
+            log_format: spec.log_format,
+            log_sinks: spec.log_sinks,
+            ansi: spec.ansi,
+            stdin: spec.stdin,
+            pty: spec.pty,
+            depends_on: spec.depends_on,
+            job: spec.job,
+            watch: spec.watch,
+            instances: spec.instances,
+            instance: spec.instance,
             pid_path: spec.pid_path,
             stdout_path: spec.stdout_path,
             stderr_path: spec.stderr_path,
@@ -42,6 +97,23 @@ impl From<ProcessDump> for ProcessSpec {
             cwd: spec.cwd,
             env: spec.env.into_iter().collect(),
             status: spec.status,
+            restart: spec.restart,
+            stop_signal: spec.stop_signal,
+            stop_timeout: spec.stop_timeout,
+            health_check: spec.health_check,
+            log_rotation: spec.log_rotation,
+            log_buffer: spec.log_buffer,
+            multiline: spec.multiline,
+            log_format: spec.log_format,
+            log_sinks: spec.log_sinks,
+            ansi: spec.ansi,
+            stdin: spec.stdin,
+            pty: spec.pty,
+            depends_on: spec.depends_on,
+            job: spec.job,
+            watch: spec.watch,
+            instances: spec.instances,
+            instance: spec.instance,
             pid_path: spec.pid_path,
             stdout_path: spec.stdout_path,
             stderr_path: spec.stderr_path,
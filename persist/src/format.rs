@@ -1,7 +1,9 @@
 use std::fmt::Display;
 use std::path::Path;
 
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
+
+use persist_core::protocol::{ExitInfo, HealthStatus, JobInfo, ProcessStatus};
 
 pub fn error(err: impl Display) {
     eprintln!("{} {}", "error:".red().bold(), err);
@@ -38,3 +40,50 @@ pub fn format_path(path: impl AsRef<Path>) -> String {
         path.display().to_string()
     }
 }
+
+pub fn format_status(status: &ProcessStatus, exit: Option<&ExitInfo>) -> ColoredString {
+    match (status, exit) {
+        (ProcessStatus::Running, _) => "running".green().bold(),
+        (ProcessStatus::Stopped, _) => "stopped".red().bold(),
+        (ProcessStatus::Scheduled, _) => "scheduled".blue().bold(),
+        (ProcessStatus::Exited, _) => "exited".yellow().bold(),
+        (ProcessStatus::Crashed, Some(exit)) => format!("crashed ({})", exit).red().bold(),
+        (ProcessStatus::Crashed, None) => "crashed".red().bold(),
+    }
+}
+
+/// Formats when the last run of a job started, along with how it ended (if it did).
+pub fn format_last_run(runs: &JobInfo, exit: Option<&ExitInfo>) -> String {
+    let Some(last_run) = runs.last_run else {
+        return "never".to_string();
+    };
+
+    let started_at = last_run.format("%Y-%m-%d %H:%M:%S");
+    match exit {
+        Some(exit) if exit.exited_at >= last_run && exit.stopped => {
+            format!("{} (stopped with {})", started_at, exit)
+        }
+        Some(exit) if exit.exited_at >= last_run => {
+            format!("{} (exited with {})", started_at, exit)
+        }
+        _ => format!("{} (still running)", started_at),
+    }
+}
+
+pub fn format_health(health: HealthStatus) -> ColoredString {
+    match health {
+        HealthStatus::Starting => "starting".blue().bold(),
+        HealthStatus::Healthy => "healthy".green().bold(),
+        HealthStatus::Unhealthy => "unhealthy".red().bold(),
+    }
+}
+
+pub fn format_duration(millis: u64) -> String {
+    let secs = millis / 1000;
+    match secs {
+        0 => format!("{}ms", millis),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
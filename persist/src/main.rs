@@ -8,6 +8,7 @@ pub mod commands;
 pub mod daemon;
 pub mod dump;
 pub mod format;
+pub mod project;
 
 use persist_core::error::Error;
 
@@ -17,7 +18,7 @@ pub enum Opts {
     /// Commands to control the daemon (advanced)
     Daemon(daemon::Opts),
     /// Start a new process
-    Start(commands::start::Opts),
+    Start(Box<commands::start::Opts>),
     /// Stop a running process
     Stop(commands::stop::Opts),
     /// Restart a process
@@ -34,12 +35,18 @@ pub enum Opts {
     List(commands::list::Opts),
     /// Access process logs
     Logs(commands::logs::Opts),
+    /// Follow the logs of a process while sending it the terminal's input
+    Attach(commands::attach::Opts),
     /// Dump configurations of currently managed processes
     Dump(commands::dump::Opts),
     /// Restore previously dumped processes
     Restore(commands::restore::Opts),
     /// Prune outdated process logs and pid files
     Prune(commands::prune::Opts),
+    /// Change the number of instances of a process
+    Scale(commands::scale::Opts),
+    /// Converge the managed processes towards the ones declared in the project file
+    Apply(commands::apply::Opts),
 }
 
 #[tokio::main]
@@ -48,7 +55,7 @@ async fn main() -> Result<(), Error> {
 
     let outcome = match opts {
         Opts::Daemon(opts) => daemon::handle(opts).await,
-        Opts::Start(opts) => commands::start::handle(opts).await,
+        Opts::Start(opts) => commands::start::handle(*opts).await,
         Opts::Stop(opts) => commands::stop::handle(opts).await,
         Opts::Restart(opts) => commands::restart::handle(opts).await,
         Opts::Info(opts) => commands::info::handle(opts).await,
@@ -56,9 +63,12 @@ async fn main() -> Result<(), Error> {
         Opts::Delete(opts) => commands::delete::handle(opts).await,
         Opts::List(opts) => commands::list::handle(opts).await,
         Opts::Logs(opts) => commands::logs::handle(opts).await,
+        Opts::Attach(opts) => commands::attach::handle(opts).await,
         Opts::Dump(opts) => commands::dump::handle(opts).await,
         Opts::Restore(opts) => commands::restore::handle(opts).await,
         Opts::Prune(opts) => commands::prune::handle(opts).await,
+        Opts::Scale(opts) => commands::scale::handle(opts).await,
+        Opts::Apply(opts) => commands::apply::handle(opts).await,
     };
 
     if let Err(err) = outcome {
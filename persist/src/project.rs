@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use persist_core::error::Error;
+use persist_core::protocol::{
+    default_log_buffer, default_stop_signal, default_stop_timeout, AnsiMode, Dependency,
+    HealthCheckSpec, InstancesSpec, JobSpec, LogFormat, LogRotationSpec, LogSink, MultilineSpec,
+    ProcessSpec, ProcessStatus, PtySpec, RestartSpec, StartRequest, WatchSpec,
+};
+
+/// The names of the project file, looked up (in order) next to the `.persist` directory.
+pub static PROJECT_FILES: &[&str] = &["persist.toml", "persist.yaml", "persist.yml"];
+
+/// A project file, declaring the processes that the daemon should manage.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Project {
+    #[serde(default)]
+    pub processes: BTreeMap<String, ProcessDecl>,
+}
+
+/// The declaration of a single process, within a project file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProcessDecl {
+    pub cmd: Vec<String>,
+    /// The working directory, relative to the directory of the project file.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Additional environment variables, on top of the ones from the current environment.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Whether the process should be managed without running.
+    #[serde(default)]
+    pub stopped: bool,
+    #[serde(default)]
+    pub restart: RestartSpec,
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: u64,
+    #[serde(default)]
+    pub health_check: Option<HealthCheckSpec>,
+    #[serde(default)]
+    pub log_rotation: LogRotationSpec,
+    #[serde(default = "default_log_buffer")]
+    pub log_buffer: usize,
+    #[serde(default)]
+    pub multiline: Option<MultilineSpec>,
+    #[serde(default)]
+    pub log_format: LogFormat,
+    #[serde(default)]
+    pub log_sinks: Vec<LogSink>,
+    #[serde(default)]
+    pub ansi: AnsiMode,
+    #[serde(default)]
+    pub stdin: bool,
+    #[serde(default)]
+    pub pty: Option<PtySpec>,
+    #[serde(default)]
+    pub depends_on: Vec<Dependency>,
+    #[serde(default)]
+    pub job: Option<JobSpec>,
+    #[serde(default)]
+    pub watch: Option<WatchSpec>,
+    #[serde(default)]
+    pub instances: Option<InstancesSpec>,
+}
+
+impl Project {
+    /// Finds the project file within the given directory.
+    pub fn find(dir: impl AsRef<Path>) -> Option<PathBuf> {
+        PROJECT_FILES
+            .iter()
+            .map(|name| dir.as_ref().join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads a project file, using its extension to determine its format (TOML or YAML).
+    pub async fn load(path: impl AsRef<Path>) -> Result<Project, Error> {
+        let path = path.as_ref();
+        let contents = tokio::fs::read_to_string(path).await?;
+
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let project = match extension {
+            Some("yaml") | Some("yml") => yaml::from_str(contents.as_str())
+                .map_err(|err| format!("invalid project file: {}", err))?,
+            _ => toml::from_str(contents.as_str())
+                .map_err(|err| format!("invalid project file: {}", err))?,
+        };
+
+        Ok(project)
+    }
+}
+
+impl ProcessDecl {
+    /// Gets the absolute working directory of the process, resolving it from the project's directory.
+    pub fn cwd(&self, root: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let cwd = match self.cwd.as_ref() {
+            Some(cwd) => root.as_ref().join(cwd),
+            None => root.as_ref().to_path_buf(),
+        };
+
+        Ok(cwd.canonicalize()?)
+    }
+
+    /// Builds the request to start the declared process.
+    pub fn to_request(&self, name: &str, root: impl AsRef<Path>) -> Result<StartRequest, Error> {
+        if self.cmd.is_empty() {
+            return Err(Error::from(format!(
+                "process '{}' has an empty command",
+                name
+            )));
+        }
+
+        let mut env: HashMap<String, String> = env::vars().collect();
+        env.extend(self.env.clone());
+        let status = match self.stopped {
+            true => ProcessStatus::Stopped,
+            false => ProcessStatus::Running,
+        };
+
+        Ok(StartRequest {
+            name: name.to_string(),
+            cmd: self.cmd.clone(),
+            cwd: self.cwd(root)?,
+            env,
+            status,
+            restart: self.restart.clone(),
+            stop_signal: self.stop_signal.clone(),
+            stop_timeout: self.stop_timeout,
+            health_check: self.health_check.clone(),
+            log_rotation: self.log_rotation.clone(),
+            log_buffer: self.log_buffer,
+            multiline: self.multiline.clone(),
+            log_format: self.log_format,
+            log_sinks: self.log_sinks.clone(),
+            ansi: self.ansi,
+            stdin: self.stdin,
+            pty: self.pty.clone(),
+            depends_on: self.depends_on.clone(),
+            job: self.job.clone(),
+            watch: self.watch.clone(),
+            instances: self.instances.clone(),
+        })
+    }
+
+    /// Lists the fields of the given specification that differ from this declaration.
+    ///
+    /// Only the environment variables declared in the project file are compared,
+    /// so that inherited ones do not cause processes to be restarted.
+    pub fn changes(&self, spec: &ProcessSpec, root: impl AsRef<Path>) -> Vec<&'static str> {
+        let mut changes = Vec::new();
+
+        if self.cmd != spec.cmd {
+            changes.push("cmd");
+        }
+        if self.cwd(root).ok().as_ref() != Some(&spec.cwd) {
+            changes.push("cwd");
+        }
+        if self
+            .env
+            .iter()
+            .any(|(key, value)| spec.env.get(key) != Some(value))
+        {
+            changes.push("env");
+        }
+        if self.restart != spec.restart {
+            changes.push("restart");
+        }
+        if self.stop_signal != spec.stop_signal {
+            changes.push("stop-signal");
+        }
+        if self.stop_timeout != spec.stop_timeout {
+            changes.push("stop-timeout");
+        }
+        if self.health_check != spec.health_check {
+            changes.push("health-check");
+        }
+        if self.log_rotation != spec.log_rotation {
+            changes.push("log-rotation");
+        }
+        if self.log_buffer != spec.log_buffer {
+            changes.push("log-buffer");
+        }
+        if self.multiline != spec.multiline {
+            changes.push("multiline");
+        }
+        if self.log_format != spec.log_format {
+            changes.push("log-format");
+        }
+        if self.log_sinks != spec.log_sinks {
+            changes.push("log-sinks");
+        }
+        if self.ansi != spec.ansi {
+            changes.push("ansi");
+        }
+        if self.stdin != spec.stdin {
+            changes.push("stdin");
+        }
+        if self.pty != spec.pty {
+            changes.push("pty");
+        }
+        if self.depends_on != spec.depends_on {
+            changes.push("depends-on");
+        }
+        if self.job != spec.job {
+            changes.push("job");
+        }
+        if self.watch != spec.watch {
+            changes.push("watch");
+        }
+        if self.instances != spec.instances {
+            changes.push("instances");
+        }
+
+        changes
+    }
+}